@@ -0,0 +1,56 @@
+//! Scanner throughput on generated megabyte-sized sources, which include non-ASCII text.
+//! Scanning is linear, so throughput should stay about the same across the sizes.
+//!
+//! Run with `cargo bench --bench scanner`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use interpreter::scanner::Scanner;
+
+const SNIPPET: &str = r#"// Tally the totals for every account.
+var total = 0;
+for (var i = 0; i < 1_000; i += 1) {
+    total = total + i * 2.5e3 - 0xFF;
+    /* nested /* block */ comment */
+    print "account ${i}: ${total} — ünïcödé ✓";
+}
+var label = """
+    multi-line
+    template
+    """;
+"#;
+
+fn source_of_size(bytes: usize) -> String {
+    let mut src = String::with_capacity(bytes + SNIPPET.len());
+    while src.len() < bytes {
+        src.push_str(SNIPPET);
+    }
+    src
+}
+
+fn bench(bytes: usize) {
+    let src = source_of_size(bytes);
+    let runs = 5;
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    for _ in 0..runs {
+        let scanner = Scanner::new(black_box(src.clone()));
+        let start = Instant::now();
+        tokens = black_box(scanner.scan_tokens()).len();
+        best = best.min(start.elapsed());
+    }
+    let megabytes = src.len() as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:>6.2} MiB  {:>9} tokens  {:>8.2} ms  {:>7.1} MiB/s",
+        megabytes,
+        tokens,
+        best.as_secs_f64() * 1000.0,
+        megabytes / best.as_secs_f64(),
+    );
+}
+
+fn main() {
+    for bytes in [64 * 1024, 1024 * 1024, 8 * 1024 * 1024] {
+        bench(bytes);
+    }
+}
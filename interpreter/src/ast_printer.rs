@@ -0,0 +1,117 @@
+use crate::expression::Expr;
+use crate::statement::Stmt;
+use crate::token::Value;
+
+const INDENT: &str = "  ";
+
+/// Prints an expression on one line as an S-expression, e.g. `(+ 1 (* 2 3))`.
+pub fn print_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(value) => literal(value),
+        Expr::Unary { operator, right } => parenthesize(operator.get_lexeme(), &[right]),
+        Expr::Binary { left, operator, right } => parenthesize(operator.get_lexeme(), &[left, right]),
+        Expr::Grouping(expr) => parenthesize("group", &[expr]),
+        Expr::Interpolation(parts) => parenthesize("interpolate", &parts.iter().collect::<Vec<_>>()),
+        Expr::Call { callee, arguments, .. } => {
+            let mut parts = vec![callee.as_ref()];
+            parts.extend(arguments);
+            parenthesize("call", &parts)
+        },
+        Expr::Index { object, index, .. } => parenthesize("index", &[object, index]),
+        Expr::Variable(name) => name.get_lexeme().to_string(),
+        Expr::Assign { name, value } => format!("(= {} {})", name.get_lexeme(), print_expr(value)),
+        Expr::CompoundAssign { name, operator, value } => {
+            format!("({} {} {})", operator.get_lexeme(), name.get_lexeme(), print_expr(value))
+        },
+        Expr::Increment { name, operator, prefix } => {
+            let fixity = if *prefix { "pre" } else { "post" };
+            format!("({}{} {})", fixity, operator.get_lexeme(), name.get_lexeme())
+        },
+        Expr::IndexAssign { object, index, operator, value, .. } => {
+            format!("({} {} {})", operator.get_lexeme(), parenthesize("index", &[object, index]), print_expr(value))
+        },
+        Expr::IndexIncrement { object, index, operator, prefix, .. } => {
+            let fixity = if *prefix { "pre" } else { "post" };
+            format!("({}{} {})", fixity, operator.get_lexeme(), parenthesize("index", &[object, index]))
+        },
+    }
+}
+
+/// Prints a statement as an S-expression, with nested statements on their own indented lines.
+pub fn print_stmt(stmt: &Stmt) -> String {
+    let mut out = String::new();
+    write_stmt(&mut out, stmt, 0);
+    out
+}
+
+/// How a literal value is written in printed trees and token dumps: strings are quoted and
+/// escaped, bigints keep their `n` suffix.
+pub fn literal(value: &Value) -> String {
+    match value {
+        Value::Integer(integer) => integer.to_string(),
+        Value::Number(number) if number.is_finite() && number.fract() == 0.0 => format!("{:.1}", number),
+        Value::Number(number) => number.to_string(),
+        Value::BigInt(bigint) => format!("{}n", bigint),
+        Value::String(string) => format!("{:?}", string),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::NativeFunction(native) => format!("<native fn {}>", native.name),
+        Value::Function(function) => format!("{:?}", function),
+        Value::List(items) => format!("[{}]", items.iter().map(literal).collect::<Vec<_>>().join(", ")),
+        Value::Nil => "nil".to_string(),
+    }
+}
+
+fn parenthesize(name: &str, exprs: &[&Expr]) -> String {
+    let mut out = format!("({}", name);
+    for expr in exprs {
+        out.push(' ');
+        out.push_str(&print_expr(expr));
+    }
+    out.push(')');
+    out
+}
+
+fn write_stmt(out: &mut String, stmt: &Stmt, depth: usize) {
+    out.push_str(&INDENT.repeat(depth));
+    match stmt {
+        Stmt::Expr(expr) => out.push_str(&format!("(expr {})", print_expr(expr))),
+        Stmt::Print(_, expr) => out.push_str(&format!("(print {})", print_expr(expr))),
+        Stmt::Var(name, None) => out.push_str(&format!("(var {})", name.get_lexeme())),
+        Stmt::Var(name, Some(initializer)) => {
+            out.push_str(&format!("(var {} {})", name.get_lexeme(), print_expr(initializer)))
+        },
+        Stmt::Block(statements) => {
+            out.push_str("(block");
+            for statement in statements {
+                out.push('\n');
+                write_stmt(out, statement, depth + 1);
+            }
+            out.push(')');
+        },
+        Stmt::If { condition, then_branch, else_branch, .. } => {
+            out.push_str(&format!("(if {}\n", print_expr(condition)));
+            write_stmt(out, then_branch, depth + 1);
+            if let Some(else_branch) = else_branch {
+                out.push('\n');
+                write_stmt(out, else_branch, depth + 1);
+            }
+            out.push(')');
+        },
+        Stmt::While { condition, body, .. } => {
+            out.push_str(&format!("(while {}\n", print_expr(condition)));
+            write_stmt(out, body, depth + 1);
+            out.push(')');
+        },
+        Stmt::Function(declaration) => {
+            let params: Vec<&str> = declaration.params.iter().map(|param| param.get_lexeme()).collect();
+            out.push_str(&format!("(fun {} ({})", declaration.name.get_lexeme(), params.join(" ")));
+            for statement in &declaration.body {
+                out.push('\n');
+                write_stmt(out, statement, depth + 1);
+            }
+            out.push(')');
+        },
+        Stmt::Return { value: None, .. } => out.push_str("(return)"),
+        Stmt::Return { value: Some(value), .. } => out.push_str(&format!("(return {})", print_expr(value))),
+    }
+}
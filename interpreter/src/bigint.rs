@@ -0,0 +1,470 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Arbitrary-precision signed integer.
+///
+/// Stored as sign and magnitude, with the magnitude in little-endian base 2^32 limbs.
+/// The magnitude never has trailing zero limbs, and zero is always non-negative, so the
+/// derived equality is exact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self { negative: false, magnitude: Vec::new() }
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        Self { negative, magnitude }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Parses an unsigned digit string in the given radix. Returns `None` on any invalid digit.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix)?;
+            mul_add_small(&mut magnitude, radix, digit);
+        }
+        Some(Self::from_parts(false, magnitude))
+    }
+
+    /// Converts an integral, finite float exactly. Returns `None` for fractions, NaN and infinities.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() || value.fract() != 0.0 {
+            return None;
+        }
+        if value.abs() < 9.2e18 {
+            return Some(Self::from(value as i64));
+        }
+        // Large integral floats are `mantissa * 2^exponent` with a non-negative exponent.
+        let bits = value.abs().to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as usize - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let magnitude = Self::from(mantissa as i64).shl(exponent);
+        Some(if value < 0.0 { -&magnitude } else { magnitude })
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let low = self.magnitude.first().copied().unwrap_or(0) as u64;
+        let high = self.magnitude.get(1).copied().unwrap_or(0) as u64;
+        let value = (high << 32) | low;
+        if self.negative {
+            if value <= i64::MAX as u64 + 1 {
+                Some((value as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(value).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude.iter().rev().fold(0.0, |acc, &limb| acc * 4294967296.0 + limb as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    fn shl(&self, bits: usize) -> Self {
+        let mut magnitude = vec![0; bits / 32];
+        magnitude.extend(shl_bits(&self.magnitude, (bits % 32) as u32));
+        Self::from_parts(self.negative, magnitude)
+    }
+
+    /// Truncating division, like Rust's `/` and `%` on primitive integers.
+    /// Returns `None` when dividing by zero.
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &other.magnitude);
+        Some((
+            Self::from_parts(self.negative != other.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+
+    /// Floored division: the quotient rounds towards negative infinity and the remainder
+    /// takes the sign of the divisor, matching `~/` and `%` on the other number types.
+    pub fn div_mod_floor(&self, other: &Self) -> Option<(Self, Self)> {
+        let (quotient, remainder) = self.div_rem(other)?;
+        if !remainder.is_zero() && remainder.negative != other.negative {
+            Some((&quotient - &Self::from(1), &remainder + other))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::from(1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+        }
+        // Opposite signs: subtract the smaller magnitude from the larger one.
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut product = vec![0u32; self.magnitude.len() + other.magnitude.len()];
+        for (i, &a) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.magnitude.iter().enumerate() {
+                let t = a as u64 * b as u64 + product[i + j] as u64 + carry;
+                product[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            product[i + other.magnitude.len()] = carry as u32;
+        }
+        BigInt::from_parts(self.negative != other.negative, product)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off base 10^9 chunks from the least significant end.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, 1_000_000_000));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &limb) in long.iter().enumerate() {
+        let t = limb as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        sum.push(t as u32);
+        carry = t >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+// Requires `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in a.iter().enumerate() {
+        let t = limb as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+        difference.push(t as u32);
+        borrow = (t < 0) as i64;
+    }
+    difference
+}
+
+fn mul_add_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let t = *limb as u64 * factor as u64 + carry;
+        *limb = t as u32;
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+// Divides in place and returns the remainder, trimming the high zero limbs.
+fn div_rem_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev() {
+        let t = (remainder << 32) | *limb as u64;
+        *limb = (t / divisor as u64) as u32;
+        remainder = t % divisor as u64;
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    remainder as u32
+}
+
+fn shl_bits(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return magnitude.to_vec();
+    }
+    let mut shifted = Vec::with_capacity(magnitude.len() + 1);
+    let mut carry = 0u32;
+    for &limb in magnitude {
+        shifted.push((limb << shift) | carry);
+        carry = limb >> (32 - shift);
+    }
+    shifted.push(carry);
+    shifted
+}
+
+fn shr_bits(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return magnitude.to_vec();
+    }
+    let mut shifted = vec![0u32; magnitude.len()];
+    for i in 0..magnitude.len() {
+        let high = magnitude.get(i + 1).copied().unwrap_or(0);
+        shifted[i] = (magnitude[i] >> shift) | (high << (32 - shift));
+    }
+    shifted
+}
+
+// Knuth's Algorithm D (TAOCP vol. 2, 4.3.1) on magnitudes. `v` must be non-zero.
+fn div_rem_magnitude(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(u, v) == Ordering::Less {
+        return (Vec::new(), u.to_vec());
+    }
+    if v.len() == 1 {
+        let mut quotient = u.to_vec();
+        let remainder = div_rem_small(&mut quotient, v[0]);
+        return (quotient, vec![remainder]);
+    }
+
+    // Normalize so the divisor's top limb has its high bit set, which keeps each
+    // quotient digit estimate within two of the true value.
+    let shift = v.last().unwrap().leading_zeros();
+    let v = shl_bits(v, shift);
+    let v = &v[..v.len() - usize::from(shift > 0)];
+    let mut u = shl_bits(u, shift);
+    if shift == 0 {
+        u.push(0);
+    }
+
+    let n = v.len();
+    let m = u.len() - n - 1;
+    let base = 1u64 << 32;
+    let mut quotient = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        let numerator = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut q_hat = numerator / v[n - 1] as u64;
+        let mut r_hat = numerator % v[n - 1] as u64;
+        while q_hat >= base || q_hat * v[n - 2] as u64 > ((r_hat << 32) | u[j + n - 2] as u64) {
+            q_hat -= 1;
+            r_hat += v[n - 1] as u64;
+            if r_hat >= base {
+                break;
+            }
+        }
+
+        // Multiply and subtract `q_hat * v` from the current window of `u`.
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = q_hat * v[i] as u64 + carry;
+            carry = product >> 32;
+            let t = u[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = t as u32;
+            borrow = (t < 0) as i64;
+        }
+        let t = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = t as u32;
+
+        // The estimate was one too large: add the divisor back.
+        if t < 0 {
+            q_hat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = q_hat as u32;
+    }
+
+    (quotient, shr_bits(&u[..n], shift))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        match text.strip_prefix('-') {
+            Some(digits) => -&BigInt::from_str_radix(digits, 10).unwrap(),
+            None => BigInt::from_str_radix(text, 10).unwrap(),
+        }
+    }
+
+    fn div_rem(a: &str, b: &str) -> (String, String) {
+        let (quotient, remainder) = big(a).div_rem(&big(b)).unwrap();
+        (quotient.to_string(), remainder.to_string())
+    }
+
+    #[test]
+    fn divides_multi_limb_numbers() {
+        assert_eq!(div_rem("1000000000000000000000000000007", "100000000000000000003"),
+            ("9999999999".to_string(), "99999999970000000010".to_string()));
+        assert_eq!(div_rem("123456789012345678901234567890", "987654321"),
+            ("124999998873437499901".to_string(), "574845669".to_string()));
+        assert_eq!(div_rem("5", "100000000000000000000"), ("0".to_string(), "5".to_string()));
+        assert_eq!(big("7").div_rem(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn adds_back_when_the_quotient_digit_is_overestimated() {
+        // 2^65 / (2^64 + 1) and 3 * 2^128 / (2^64 + 1) both take the add-back step.
+        assert_eq!(div_rem("36893488147419103232", "18446744073709551617"),
+            ("1".to_string(), "18446744073709551615".to_string()));
+        assert_eq!(div_rem("1020847100762815390390123822295304634368", "18446744073709551617"),
+            ("55340232221128654845".to_string(), "3".to_string()));
+    }
+
+    #[test]
+    fn floors_division_for_every_sign() {
+        let cases = [
+            ("1000000000000000000000000000007", "100000000000000000003", "9999999999", "99999999970000000010"),
+            ("-1000000000000000000000000000007", "100000000000000000003", "-10000000000", "29999999993"),
+            ("1000000000000000000000000000007", "-100000000000000000003", "-10000000000", "-29999999993"),
+            ("-1000000000000000000000000000007", "-100000000000000000003", "9999999999", "-99999999970000000010"),
+            ("-6", "3", "-2", "0"),
+        ];
+        for (a, b, quotient, remainder) in cases {
+            let (q, r) = big(a).div_mod_floor(&big(b)).unwrap();
+            assert_eq!((q.to_string(), r.to_string()), (quotient.to_string(), remainder.to_string()), "{} / {}", a, b);
+        }
+    }
+
+    #[test]
+    fn converts_to_i64_only_within_range() {
+        assert_eq!(BigInt::from(i64::MAX).to_i64(), Some(i64::MAX));
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+        assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(big("36893488147419103232").to_i64(), None);
+    }
+
+    #[test]
+    fn converts_integral_floats_exactly() {
+        assert_eq!(BigInt::from_f64(2f64.powi(80)), Some(big("1208925819614629174706176")));
+        assert_eq!(BigInt::from_f64(-1.5e20), Some(big("-150000000000000000000")));
+        assert_eq!(BigInt::from_f64(-42.0), Some(big("-42")));
+        assert_eq!(BigInt::from_f64(2.5), None);
+        assert_eq!(BigInt::from_f64(1e20 + 0.5), Some(big("100000000000000000000")));
+        assert_eq!(BigInt::from_f64(f64::NAN), None);
+        assert_eq!(BigInt::from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn displays_zero_and_negatives() {
+        assert_eq!(BigInt::zero().to_string(), "0");
+        assert_eq!((-&BigInt::zero()).to_string(), "0");
+        assert_eq!(BigInt::from(-1).to_string(), "-1");
+        assert_eq!(big("-123456789012345678901234567890").to_string(), "-123456789012345678901234567890");
+        assert_eq!((&big("5") - &big("5")).to_string(), "0");
+    }
+
+    #[test]
+    fn carries_past_the_range_of_i64() {
+        let one = BigInt::from(1);
+        assert_eq!((&BigInt::from(i64::MAX) + &one).to_string(), "9223372036854775808");
+        assert_eq!((&BigInt::from(i64::MIN) - &one).to_string(), "-9223372036854775809");
+        assert_eq!((-&BigInt::from(i64::MIN)).to_string(), "9223372036854775808");
+        assert_eq!((&BigInt::from(i64::MIN) * &BigInt::from(-1)).to_string(), "9223372036854775808");
+        assert_eq!((&BigInt::from(i64::MAX) * &BigInt::from(i64::MAX)).to_string(), "85070591730234615847396907784232501249");
+        assert_eq!(BigInt::from(2).pow(64).to_string(), "18446744073709551616");
+        assert_eq!(BigInt::from(-3).pow(3).to_string(), "-27");
+    }
+}
@@ -0,0 +1,479 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+use crate::ast_printer;
+use crate::debugger::{Debugger, Pause, Reason, Resume};
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::json::{self, Json};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::statement::Stmt;
+use crate::token::Value;
+
+// Scripts run on a single thread, which is the only one ever reported.
+const THREAD_ID: i64 = 1;
+
+/// Serves the Debug Adapter Protocol on `input` and `output` until the client disconnects.
+///
+/// The session follows the usual launch sequence: `initialize`, then `launch` with a `program`
+/// path (and optionally `args` and `stopOnEntry`), `setBreakpoints` and `configurationDone`,
+/// after which the script runs. It pauses at breakpoints and after steps, where the client
+/// can ask for the stack, scopes, variables and evaluate expressions. Requests are only read
+/// while the script is paused, so `pause` is refused: a running script can't be interrupted.
+pub fn run_server(input: impl BufRead + 'static, output: impl Write + 'static) {
+    let connection = Rc::new(RefCell::new(Connection { input: Box::new(input), output: Box::new(output), seq: 0 }));
+    let mut launch = None;
+    let mut breakpoints = BTreeSet::new();
+    let mut configured = false;
+
+    while launch.is_none() || !configured {
+        let Some(request) = connection.borrow_mut().read() else {
+            return;
+        };
+        let mut connection = connection.borrow_mut();
+        match command(&request) {
+            "initialize" => {
+                connection.respond(&request, Json::object(vec![
+                    ("supportsConfigurationDoneRequest", Json::Bool(true)),
+                    ("supportsEvaluateForHovers", Json::Bool(true)),
+                ]));
+                connection.event("initialized", Json::object(vec![]));
+            },
+            "launch" => {
+                if argument(&request, "program").and_then(Json::as_str).is_some() {
+                    launch = request.get("arguments").cloned();
+                    connection.respond(&request, Json::Null);
+                } else {
+                    connection.fail(&request, "Missing 'program' in launch arguments.");
+                }
+            },
+            "setBreakpoints" => {
+                let lines = argument(&request, "source")
+                    .and_then(|source| source.get("path")?.as_str())
+                    .and_then(|path| fs::read_to_string(path).ok())
+                    .map(|src| statement_lines(&Parser::with_error_handler(Scanner::new(src), |_| {}).parse()))
+                    .unwrap_or_default();
+                let (placed, body) = place_breakpoints(&request, &lines);
+                breakpoints = placed;
+                connection.respond(&request, body);
+            },
+            "setExceptionBreakpoints" => connection.respond(&request, Json::Null),
+            "threads" => connection.respond(&request, threads_body()),
+            "configurationDone" => {
+                configured = true;
+                connection.respond(&request, Json::Null);
+            },
+            "disconnect" | "terminate" => {
+                connection.respond(&request, Json::Null);
+                return;
+            },
+            command => connection.fail(&request, &format!("Unsupported request '{}'.", command)),
+        }
+    }
+
+    let launch = launch.unwrap();
+    let program = launch.get("program").and_then(Json::as_str).unwrap_or_default().to_string();
+    let stop_on_entry = launch.get("stopOnEntry").and_then(Json::as_bool).unwrap_or(false);
+    let script_args: Vec<Value> = launch.get("args").and_then(Json::as_array).unwrap_or_default()
+        .iter()
+        .filter_map(|arg| arg.as_str().map(|arg| Value::String(arg.to_string())))
+        .collect();
+    let Some(exit_code) = run_program(&connection, &program, script_args, breakpoints, stop_on_entry) else {
+        return;
+    };
+    connection.borrow_mut().event("exited", Json::object(vec![("exitCode", Json::Integer(exit_code))]));
+    connection.borrow_mut().event("terminated", Json::object(vec![]));
+    // The client may still ask about threads before it disconnects.
+    loop {
+        let Some(request) = connection.borrow_mut().read() else {
+            return;
+        };
+        match command(&request) {
+            "threads" => connection.borrow_mut().respond(&request, threads_body()),
+            "disconnect" | "terminate" => {
+                connection.borrow_mut().respond(&request, Json::Null);
+                return;
+            },
+            _ => connection.borrow_mut().fail(&request, "The script has finished."),
+        }
+    }
+}
+
+// Runs the launched script and returns the exit code running it from the command line would
+// give, or `None` if the client ended the session while it was paused.
+fn run_program(connection: &Rc<RefCell<Connection>>, program: &str, script_args: Vec<Value>, breakpoints: BTreeSet<usize>, stop_on_entry: bool) -> Option<i64> {
+    let src = match fs::read_to_string(program) {
+        Ok(src) => src,
+        Err(err) => {
+            connection.borrow_mut().output("stderr", &format!("{}: {}\n", program, err));
+            return Some(66);
+        },
+    };
+    let errors = connection.clone();
+    let mut parser = Parser::with_error_handler(Scanner::new(src), move |error| {
+        errors.borrow_mut().output("stderr", &format!("{}\n", error));
+    });
+    // Like on the command line, a script with syntax errors doesn't run at all.
+    let statements = parser.parse();
+    if parser.had_error() {
+        return Some(65);
+    }
+    let session = Session {
+        connection: connection.clone(),
+        program: program.to_string(),
+        lines: statement_lines(&statements),
+        handles: Vec::new(),
+    };
+    let mut interpreter = Interpreter::new();
+    interpreter.define_global("args", Value::List(Rc::new(script_args)));
+    interpreter.set_output(OutputEvents(connection.clone()));
+    interpreter.set_debugger(Debugger::adapter(session, breakpoints, stop_on_entry));
+    let mut failed = false;
+    for stmt in &statements {
+        if let Err(error) = interpreter.run(std::slice::from_ref(stmt)) {
+            if interpreter.stopped() {
+                return None;
+            }
+            connection.borrow_mut().output("stderr", &format!("Runtime error: {}\n", error));
+            failed = true;
+        }
+    }
+    Some(if failed { 70 } else { 0 })
+}
+
+fn command(request: &Json) -> &str {
+    request.get("command").and_then(Json::as_str).unwrap_or_default()
+}
+
+fn argument<'a>(request: &'a Json, name: &str) -> Option<&'a Json> {
+    request.get("arguments")?.get(name)
+}
+
+// The lines a breakpoint can stop on: those where a statement other than a block starts.
+fn statement_lines(statements: &[Stmt]) -> BTreeSet<usize> {
+    fn collect(statements: &[Stmt], lines: &mut BTreeSet<usize>) {
+        for stmt in statements {
+            if !matches!(stmt, Stmt::Block(_)) {
+                lines.extend(stmt.line());
+            }
+            match stmt {
+                Stmt::Block(statements) => collect(statements, lines),
+                Stmt::If { then_branch, else_branch, .. } => {
+                    collect(std::slice::from_ref(then_branch), lines);
+                    if let Some(else_branch) = else_branch {
+                        collect(std::slice::from_ref(else_branch), lines);
+                    }
+                },
+                Stmt::While { body, .. } => collect(std::slice::from_ref(body), lines),
+                Stmt::Function(declaration) => collect(&declaration.body, lines),
+                _ => {},
+            }
+        }
+    }
+    let mut lines = BTreeSet::new();
+    collect(statements, &mut lines);
+    lines
+}
+
+// Moves each requested breakpoint to the first statement on or after its line. Those past the
+// last statement are reported unverified. Returns the lines to stop on and the response body,
+// which lists the breakpoints in the order they were requested.
+fn place_breakpoints(request: &Json, lines: &BTreeSet<usize>) -> (BTreeSet<usize>, Json) {
+    let mut placed = BTreeSet::new();
+    let breakpoints = argument(request, "breakpoints").and_then(Json::as_array).unwrap_or_default()
+        .iter()
+        .filter_map(|breakpoint| breakpoint.get("line")?.as_i64())
+        .filter_map(|line| usize::try_from(line).ok())
+        .map(|line| match lines.range(line..).next() {
+            Some(&line) => {
+                placed.insert(line);
+                Json::object(vec![("verified", Json::Bool(true)), ("line", Json::Integer(line as i64))])
+            },
+            None => Json::object(vec![
+                ("verified", Json::Bool(false)),
+                ("line", Json::Integer(line as i64)),
+                ("message", Json::String("No statement on or after this line.".to_string())),
+            ]),
+        })
+        .collect();
+    (placed, Json::object(vec![("breakpoints", Json::Array(breakpoints))]))
+}
+
+fn threads_body() -> Json {
+    let thread = Json::object(vec![("id", Json::Integer(THREAD_ID)), ("name", Json::String("main".to_string()))]);
+    Json::object(vec![("threads", Json::Array(vec![thread]))])
+}
+
+// The client's end of the protocol: messages framed by a `Content-Length` header.
+struct Connection {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    seq: i64,
+}
+
+impl Connection {
+    // The next request, or `None` once the client has gone.
+    fn read(&mut self) -> Option<Json> {
+        loop {
+            let mut length = None;
+            loop {
+                let mut header = String::new();
+                if self.input.read_line(&mut header).ok()? == 0 {
+                    return None;
+                }
+                let header = header.trim();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Content-Length:") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+            let mut body = vec![0; length?];
+            self.input.read_exact(&mut body).ok()?;
+            match json::parse(&String::from_utf8_lossy(&body)) {
+                Ok(message) => return Some(message),
+                Err(error) => eprintln!("Ignoring malformed message: {}", error),
+            }
+        }
+    }
+
+    fn send(&mut self, kind: &str, mut fields: Vec<(&str, Json)>) {
+        self.seq += 1;
+        fields.insert(0, ("seq", Json::Integer(self.seq)));
+        fields.insert(1, ("type", Json::String(kind.to_string())));
+        let body = Json::object(fields).to_string();
+        let _ = write!(self.output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = self.output.flush();
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) {
+        self.send("response", vec![
+            ("request_seq", request.get("seq").cloned().unwrap_or(Json::Null)),
+            ("success", Json::Bool(true)),
+            ("command", Json::String(command(request).to_string())),
+            ("body", body),
+        ]);
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) {
+        self.send("response", vec![
+            ("request_seq", request.get("seq").cloned().unwrap_or(Json::Null)),
+            ("success", Json::Bool(false)),
+            ("command", Json::String(command(request).to_string())),
+            ("message", Json::String(message.to_string())),
+        ]);
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send("event", vec![("event", Json::String(event.to_string())), ("body", body)]);
+    }
+
+    fn output(&mut self, category: &str, text: &str) {
+        self.event("output", Json::object(vec![
+            ("category", Json::String(category.to_string())),
+            ("output", Json::String(text.to_string())),
+        ]));
+    }
+}
+
+// Forwards what the script prints as `output` events, since stdout carries the protocol.
+struct OutputEvents(Rc<RefCell<Connection>>);
+
+impl Write for OutputEvents {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().output("stdout", &String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// What a `variablesReference` points at. References are only valid until the script resumes.
+enum Handle {
+    Scope(Rc<RefCell<Environment>>),
+    List(Rc<Vec<Value>>),
+}
+
+/// The debugger frontend that answers a client's requests while the script is paused.
+pub(crate) struct Session {
+    connection: Rc<RefCell<Connection>>,
+    program: String,
+    // Where breakpoints can go.
+    lines: BTreeSet<usize>,
+    handles: Vec<Handle>,
+}
+
+impl Session {
+    pub(crate) fn pause(&mut self, interpreter: &mut Interpreter, pause: Pause) -> Resume {
+        let reason = match pause.reason {
+            Reason::Entry => "entry",
+            Reason::Step => "step",
+            Reason::Breakpoint => "breakpoint",
+        };
+        self.connection.borrow_mut().event("stopped", Json::object(vec![
+            ("reason", Json::String(reason.to_string())),
+            ("threadId", Json::Integer(THREAD_ID)),
+            ("allThreadsStopped", Json::Bool(true)),
+        ]));
+        let resume = self.serve(interpreter, pause);
+        self.handles.clear();
+        resume
+    }
+
+    fn serve(&mut self, interpreter: &mut Interpreter, pause: Pause) -> Resume {
+        loop {
+            let Some(request) = self.connection.borrow_mut().read() else {
+                return Resume::Detach;
+            };
+            let resume = match command(&request) {
+                "continue" => Some(Resume::Continue),
+                "next" => Some(Resume::Next),
+                "stepIn" => Some(Resume::Step),
+                "stepOut" => Some(Resume::Out),
+                _ => None,
+            };
+            if let Some(resume) = resume {
+                let body = Json::object(vec![("allThreadsContinued", Json::Bool(true))]);
+                self.connection.borrow_mut().respond(&request, body);
+                return resume;
+            }
+            let result = match command(&request) {
+                "threads" => Ok(threads_body()),
+                "stackTrace" => Ok(self.stack_trace(interpreter, &pause)),
+                "scopes" => self.scopes(interpreter, &pause, &request),
+                "variables" => self.variables(&request),
+                "evaluate" => self.evaluate(interpreter, &pause, &request),
+                "setBreakpoints" => {
+                    let (placed, body) = place_breakpoints(&request, &self.lines);
+                    *pause.breakpoints = placed;
+                    Ok(body)
+                },
+                "setExceptionBreakpoints" => Ok(Json::Null),
+                "pause" => Err("Pausing is not supported; set a breakpoint instead.".to_string()),
+                "disconnect" | "terminate" => {
+                    self.connection.borrow_mut().respond(&request, Json::Null);
+                    return Resume::Stop;
+                },
+                command => Err(format!("Unsupported request '{}'.", command)),
+            };
+            match result {
+                Ok(body) => self.connection.borrow_mut().respond(&request, body),
+                Err(message) => self.connection.borrow_mut().fail(&request, &message),
+            }
+        }
+    }
+
+    fn stack_trace(&self, interpreter: &Interpreter, pause: &Pause) -> Json {
+        let name = Path::new(&self.program).file_name().map_or(self.program.clone(), |name| name.to_string_lossy().to_string());
+        let source = Json::object(vec![
+            ("name", Json::String(name)),
+            ("path", Json::String(self.program.clone())),
+        ]);
+        let frames: Vec<Json> = pause.stack(interpreter).iter().enumerate().map(|(id, (function, line, _))| {
+            Json::object(vec![
+                ("id", Json::Integer(id as i64)),
+                ("name", Json::String(function.unwrap_or("<script>").to_string())),
+                ("source", source.clone()),
+                ("line", Json::Integer(*line as i64)),
+                ("column", Json::Integer(1)),
+            ])
+        }).collect();
+        Json::object(vec![("totalFrames", Json::Integer(frames.len() as i64)), ("stackFrames", Json::Array(frames))])
+    }
+
+    // The scope of the frame to look in: the innermost one unless the request names another.
+    fn frame_environment(interpreter: &Interpreter, pause: &Pause, request: &Json) -> Result<Rc<RefCell<Environment>>, String> {
+        let id = argument(request, "frameId").and_then(Json::as_i64).unwrap_or(0);
+        let stack = pause.stack(interpreter);
+        usize::try_from(id).ok()
+            .and_then(|id| stack.into_iter().nth(id))
+            .map(|(_, _, environment)| environment)
+            .ok_or_else(|| format!("Unknown frame {}.", id))
+    }
+
+    // One scope per environment in the frame's parent chain, innermost first.
+    fn scopes(&mut self, interpreter: &Interpreter, pause: &Pause, request: &Json) -> Result<Json, String> {
+        let mut environment = Some(Self::frame_environment(interpreter, pause, request)?);
+        let mut scopes = Vec::new();
+        let mut depth = 0;
+        while let Some(current) = environment {
+            let parent = current.borrow().parent();
+            let name = match (depth, &parent) {
+                (_, None) => "Globals".to_string(),
+                (0, _) => "Locals".to_string(),
+                (depth, _) => format!("Enclosing {}", depth),
+            };
+            let reference = self.handle(Handle::Scope(current));
+            scopes.push(Json::object(vec![
+                ("name", Json::String(name)),
+                ("variablesReference", Json::Integer(reference)),
+                ("expensive", Json::Bool(false)),
+            ]));
+            environment = parent;
+            depth += 1;
+        }
+        Ok(Json::object(vec![("scopes", Json::Array(scopes))]))
+    }
+
+    fn variables(&mut self, request: &Json) -> Result<Json, String> {
+        let reference = argument(request, "variablesReference").and_then(Json::as_i64).unwrap_or(0);
+        let bindings: Vec<(String, Value)> = match usize::try_from(reference - 1).ok().and_then(|index| self.handles.get(index)) {
+            // Natives are always there, so they are left out to keep the globals readable.
+            Some(Handle::Scope(environment)) => environment.borrow().bindings().into_iter()
+                .filter(|(_, value)| !matches!(value, Value::NativeFunction(_)))
+                .collect(),
+            Some(Handle::List(items)) => items.iter().enumerate().map(|(index, item)| (index.to_string(), item.clone())).collect(),
+            None => return Err(format!("Unknown variables reference {}.", reference)),
+        };
+        let variables = bindings.into_iter().map(|(name, value)| {
+            let mut fields = vec![("name", Json::String(name))];
+            fields.extend(self.describe(&value, "value"));
+            Json::object(fields)
+        }).collect();
+        Ok(Json::object(vec![("variables", Json::Array(variables))]))
+    }
+
+    fn evaluate(&mut self, interpreter: &mut Interpreter, pause: &Pause, request: &Json) -> Result<Json, String> {
+        let expression = argument(request, "expression").and_then(Json::as_str).unwrap_or_default();
+        let environment = Self::frame_environment(interpreter, pause, request)?;
+        // Syntax errors go back to the client, as stderr isn't part of the protocol.
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let sink = errors.clone();
+        let statements = Parser::with_error_handler(Scanner::new(expression.to_string()), move |error| {
+            sink.borrow_mut().push(error.to_string());
+        }).interactive().parse();
+        if !errors.borrow().is_empty() {
+            return Err(errors.borrow().join("\n"));
+        }
+        let [Stmt::Expr(expr)] = statements.as_slice() else {
+            return Err(format!("Cannot evaluate '{}': not an expression.", expression));
+        };
+        let value = interpreter.evaluate_in(environment, expr)?;
+        Ok(Json::object(self.describe(&value, "result")))
+    }
+
+    // The fields that present a value, under `key`, with a reference to its items if it has any.
+    fn describe<'a>(&mut self, value: &Value, key: &'a str) -> Vec<(&'a str, Json)> {
+        let reference = match value {
+            Value::List(items) if !items.is_empty() => self.handle(Handle::List(items.clone())),
+            _ => 0,
+        };
+        vec![
+            (key, Json::String(ast_printer::literal(value))),
+            ("type", Json::String(value.type_name().to_string())),
+            ("variablesReference", Json::Integer(reference)),
+        ]
+    }
+
+    fn handle(&mut self, handle: Handle) -> i64 {
+        self.handles.push(handle);
+        self.handles.len() as i64
+    }
+}
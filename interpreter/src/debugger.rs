@@ -0,0 +1,284 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+use crate::ast_printer;
+use crate::dap;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::line_editor::{LineEditor, ReadLine};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::statement::Stmt;
+use crate::token::Value;
+
+const PROMPT: &str = "(debug) ";
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("step, s", "run to the next statement, entering calls"),
+    ("next, n", "run to the next statement in this function or its callers"),
+    ("continue, c", "run until a breakpoint is reached"),
+    ("break, b [line]", "stop before the first statement on a line, or list breakpoints"),
+    ("delete, d <line>", "remove a breakpoint"),
+    ("print, p <expr>", "evaluate an expression in the current scope"),
+    ("locals", "list the variables of the current function or block"),
+    ("backtrace, bt", "list the calls that led here"),
+    ("help", "show this list"),
+    ("quit, q", "stop the script"),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Step,
+    // Stop once the call stack is no deeper than this.
+    Next(usize),
+    // Stop once the call stack is shallower than this.
+    Out(usize),
+    Continue,
+    // The frontend ended the session: no statement runs anymore.
+    Stopped,
+}
+
+/// How a frontend lets the script carry on after a pause.
+pub(crate) enum Resume {
+    Step,
+    Next,
+    Out,
+    Continue,
+    // Run to the end without stopping again.
+    Detach,
+    // Stop running the script.
+    Stop,
+}
+
+/// What the interpreter does with the statement it is about to run.
+pub(crate) enum Action {
+    Run,
+    // Run it, and every statement after it, without consulting the debugger again.
+    Detach,
+    // Abandon it, and every statement after it.
+    Stop,
+}
+
+/// Why the script paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    Entry,
+    Step,
+    Breakpoint,
+}
+
+/// A call in progress: the function's name, the line it was called from and the scope it was
+/// called from.
+pub(crate) struct Frame {
+    pub name: String,
+    pub line: usize,
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+/// Where the script is paused and what a frontend may change while it is.
+pub(crate) struct Pause<'a> {
+    pub line: usize,
+    pub reason: Reason,
+    pub frames: &'a [Frame],
+    pub breakpoints: &'a mut BTreeSet<usize>,
+}
+
+/// A frame's function name (`None` for the top level), current line and scope.
+pub(crate) type StackFrame<'a> = (Option<&'a str>, usize, Rc<RefCell<Environment>>);
+
+impl Pause<'_> {
+    /// The call stack, innermost first.
+    pub fn stack(&self, interpreter: &Interpreter) -> Vec<StackFrame<'_>> {
+        let mut stack = Vec::new();
+        let (mut line, mut environment) = (self.line, interpreter.environment());
+        for frame in self.frames.iter().rev() {
+            stack.push((Some(frame.name.as_str()), line, environment));
+            (line, environment) = (frame.line, frame.environment.clone());
+        }
+        stack.push((None, line, environment));
+        stack
+    }
+}
+
+enum Frontend {
+    Console(Console),
+    Adapter(dap::Session),
+}
+
+/// An interactive debugger. Once attached with `Interpreter::set_debugger`, it is consulted
+/// before each statement and hands control to its frontend, a console prompt or a debug
+/// adapter, when stepping or at a breakpoint.
+pub struct Debugger {
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    frames: Vec<Frame>,
+    // The line of the last statement run, so that a breakpoint stops once per visit to its line
+    // rather than once per statement on it.
+    last_line: usize,
+    // Whether nothing has run yet, so that a pause is reported as the entry point.
+    at_entry: bool,
+    frontend: Frontend,
+}
+
+impl Debugger {
+    /// A debugger driven from a prompt on stdin, which appears before the first statement runs.
+    pub fn new(source: &str) -> Self {
+        let console = Console {
+            source: source.lines().map(str::to_string).collect(),
+            last_command: String::new(),
+            editor: LineEditor::new(None),
+        };
+        Self::with_frontend(Frontend::Console(console), BTreeSet::new(), true)
+    }
+
+    pub(crate) fn adapter(session: dap::Session, breakpoints: BTreeSet<usize>, stop_on_entry: bool) -> Self {
+        Self::with_frontend(Frontend::Adapter(session), breakpoints, stop_on_entry)
+    }
+
+    fn with_frontend(frontend: Frontend, breakpoints: BTreeSet<usize>, stop_on_entry: bool) -> Self {
+        let mode = if stop_on_entry { Mode::Step } else { Mode::Continue };
+        Self { breakpoints, mode, frames: Vec::new(), last_line: 0, at_entry: true, frontend }
+    }
+
+    pub(crate) fn enter_call(&mut self, name: &str, line: usize, environment: Rc<RefCell<Environment>>) {
+        self.frames.push(Frame { name: name.to_string(), line, environment });
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Whether the frontend has stopped the script.
+    pub(crate) fn stopped(&self) -> bool {
+        matches!(self.mode, Mode::Stopped)
+    }
+
+    /// Called before each statement.
+    pub(crate) fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> Action {
+        if self.stopped() {
+            return Action::Stop;
+        }
+        // Blocks only hold other statements, which get their own turn.
+        let Some(line) = stmt.line().filter(|_| !matches!(stmt, Stmt::Block(_))) else {
+            return Action::Run;
+        };
+        let at_breakpoint = self.breakpoints.contains(&line) && line != self.last_line;
+        let stop = match self.mode {
+            Mode::Step => true,
+            Mode::Next(depth) => self.frames.len() <= depth || at_breakpoint,
+            Mode::Out(depth) => self.frames.len() < depth || at_breakpoint,
+            Mode::Continue => at_breakpoint,
+            Mode::Stopped => unreachable!(),
+        };
+        let reason = if at_breakpoint {
+            Reason::Breakpoint
+        } else if self.at_entry {
+            Reason::Entry
+        } else {
+            Reason::Step
+        };
+        self.last_line = line;
+        self.at_entry = false;
+        if !stop {
+            return Action::Run;
+        }
+        let pause = Pause { line, reason, frames: &self.frames, breakpoints: &mut self.breakpoints };
+        let resume = match &mut self.frontend {
+            Frontend::Console(console) => console.pause(interpreter, pause),
+            Frontend::Adapter(session) => session.pause(interpreter, pause),
+        };
+        self.mode = match resume {
+            Resume::Step => Mode::Step,
+            Resume::Next => Mode::Next(self.frames.len()),
+            Resume::Out => Mode::Out(self.frames.len()),
+            Resume::Continue => Mode::Continue,
+            Resume::Detach => return Action::Detach,
+            Resume::Stop => Mode::Stopped,
+        };
+        if self.stopped() { Action::Stop } else { Action::Run }
+    }
+}
+
+struct Console {
+    source: Vec<String>,
+    last_command: String,
+    editor: LineEditor,
+}
+
+impl Console {
+    // Reads commands until one of them resumes the script.
+    fn pause(&mut self, interpreter: &mut Interpreter, pause: Pause) -> Resume {
+        println!("=> {}: {}", pause.line, self.source.get(pause.line - 1).map_or("", |text| text.trim()));
+        loop {
+            let input = match self.editor.read_line(PROMPT, &[]) {
+                Ok(ReadLine::Line(input)) => input,
+                Ok(ReadLine::Interrupted) => continue,
+                Ok(ReadLine::Eof) | Err(_) => return Resume::Detach,
+            };
+            self.editor.add_history(&input);
+            // An empty line repeats the last command, so that stepping is a matter of pressing Enter.
+            let input = if input.trim().is_empty() { self.last_command.clone() } else { input.trim().to_string() };
+            self.last_command = input.clone();
+            let (command, argument) = input.split_once(char::is_whitespace).unwrap_or((&input, ""));
+            let argument = argument.trim();
+            match command {
+                "step" | "s" => return Resume::Step,
+                "next" | "n" => return Resume::Next,
+                "continue" | "c" => return Resume::Continue,
+                "break" | "b" if argument.is_empty() => {
+                    for breakpoint in pause.breakpoints.iter() {
+                        println!("Breakpoint at line {}", breakpoint);
+                    }
+                },
+                "break" | "b" | "delete" | "d" => match argument.parse::<usize>() {
+                    Ok(breakpoint) if command.starts_with('b') => {
+                        pause.breakpoints.insert(breakpoint);
+                        println!("Breakpoint at line {}", breakpoint);
+                    },
+                    Ok(breakpoint) if pause.breakpoints.remove(&breakpoint) => println!("Deleted breakpoint at line {}", breakpoint),
+                    Ok(breakpoint) => println!("No breakpoint at line {}", breakpoint),
+                    Err(_) => println!("Usage: {} <line>", command),
+                },
+                "print" | "p" => print(interpreter, argument),
+                "locals" => locals(interpreter),
+                "backtrace" | "bt" => {
+                    for (depth, (name, line, _)) in pause.stack(interpreter).iter().enumerate() {
+                        println!("#{} {} at line {}", depth, name.unwrap_or("<script>"), line);
+                    }
+                },
+                "help" => {
+                    for (usage, description) in COMMANDS {
+                        println!("{:<18} {}", usage, description);
+                    }
+                },
+                "quit" | "q" => return Resume::Stop,
+                _ => println!("Unknown command '{}'. Type help for a list.", command),
+            }
+        }
+    }
+}
+
+fn print(interpreter: &mut Interpreter, src: &str) {
+    match Parser::repl(Scanner::new(src.to_string())).next() {
+        Some(Stmt::Expr(expr)) => match interpreter.evaluate(&expr) {
+            Ok(value) => println!("{}", ast_printer::literal(&value)),
+            Err(error) => println!("Runtime error: {}", error),
+        },
+        Some(_) => println!("Usage: print <expression>"),
+        None => {},
+    }
+}
+
+// The scopes of the current function or block, innermost first. At the top level that is the
+// globals, less the natives.
+fn locals(interpreter: &Interpreter) {
+    let mut scopes = interpreter.scopes();
+    if scopes.len() > 1 {
+        scopes.pop();
+    }
+    for (name, value) in scopes.iter().flatten() {
+        if !matches!(value, Value::NativeFunction(_)) {
+            println!("{} = {}", name, ast_printer::literal(value));
+        }
+    }
+}
@@ -1,10 +1,9 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::expression::Expr;
 use crate::token::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Environment {
     values: HashMap<String, Value>,
     parent: Option<Rc<RefCell<Environment>>>,
@@ -34,6 +33,37 @@ impl Environment {
         Err(format!("Runtime error: Variable {} not defined", name.get_lexeme()))
     }
 
+    /// The bindings of this scope alone, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        let mut bindings: Vec<(String, Value)> = self.values.iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
+    pub fn parent(&self) -> Option<Rc<RefCell<Environment>>> {
+        self.parent.clone()
+    }
+
+    /// The bindings of each scope from this one outwards, each sorted by name.
+    pub fn scopes(&self) -> Vec<Vec<(String, Value)>> {
+        let mut scopes = vec![self.bindings()];
+        if let Some(parent) = &self.parent {
+            scopes.extend(parent.borrow().scopes());
+        }
+        scopes
+    }
+
+    /// Every name visible from this scope, including those of enclosing scopes.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.keys().cloned().collect();
+        if let Some(parent) = &self.parent {
+            names.extend(parent.borrow().names());
+        }
+        names
+    }
+
     pub fn get(&self, name: &Token) -> Result<Value, String> {
         match self.values.get(name.get_lexeme()) {
             Some(existing_value) => Ok(existing_value.clone()),
@@ -0,0 +1,367 @@
+use std::rc::Rc;
+use crate::bigint::BigInt;
+use crate::expression::Expr;
+use crate::json::Json;
+use crate::scanner::ScanError;
+use crate::statement::{FunctionDecl, Stmt};
+use crate::token::{Token, TokenType, Value};
+
+/// Version of the JSON layout described in `docs/json-schema.md`. Bumped whenever a field is
+/// renamed or removed; adding fields keeps the version.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Exports a token stream as `{"version": 1, "tokens": [...], "errors": [...]}`.
+pub fn export_tokens(tokens: impl IntoIterator<Item = Result<Token, ScanError>>) -> Json {
+    let mut exported = Vec::new();
+    let mut errors = Vec::new();
+    for token in tokens {
+        match token {
+            Ok(token) => exported.push(token_to_json(&token)),
+            Err(error) => errors.push(Json::String(error.to_string())),
+        }
+    }
+    Json::object(vec![
+        ("version", Json::Integer(SCHEMA_VERSION)),
+        ("tokens", Json::Array(exported)),
+        ("errors", Json::Array(errors)),
+    ])
+}
+
+/// Exports parsed statements as `{"version": 1, "statements": [...]}`.
+pub fn export_program<'a>(statements: impl IntoIterator<Item = &'a Stmt>) -> Json {
+    Json::object(vec![
+        ("version", Json::Integer(SCHEMA_VERSION)),
+        ("statements", Json::Array(statements.into_iter().map(stmt_to_json).collect())),
+    ])
+}
+
+/// Rebuilds statements from a document produced by `export_program`.
+pub fn import_program(document: &Json) -> Result<Vec<Stmt>, String> {
+    match document.get("version").and_then(Json::as_i64) {
+        Some(SCHEMA_VERSION) => {},
+        Some(version) => return Err(format!("Unsupported schema version {}.", version)),
+        None => return Err("Missing schema version.".to_string()),
+    }
+    array(document, "statements")?.iter().map(stmt_from_json).collect()
+}
+
+fn token_to_json(token: &Token) -> Json {
+    Json::object(vec![
+        ("type", Json::String(format!("{:?}", token.get_type()))),
+        ("lexeme", Json::String(token.get_lexeme().to_string())),
+        ("literal", token.get_literal().map(value_to_json).unwrap_or(Json::Null)),
+        ("line", Json::Integer(token.get_line() as i64)),
+        ("column", Json::Integer(token.get_column() as i64)),
+    ])
+}
+
+fn value_to_json(value: &Value) -> Json {
+    let (kind, value) = match value {
+        Value::Integer(integer) => ("integer", Json::Integer(*integer)),
+        Value::Number(number) if number.is_finite() => ("number", Json::Float(*number)),
+        Value::Number(number) => ("number", Json::String(number.to_string())),
+        Value::BigInt(bigint) => ("bigint", Json::String(bigint.to_string())),
+        Value::String(string) => ("string", Json::String(string.clone())),
+        Value::Boolean(boolean) => ("boolean", Json::Bool(*boolean)),
+        Value::NativeFunction(native) => ("native", Json::String(native.name.to_string())),
+        Value::Function(function) => ("function", Json::String(function.declaration.name.get_lexeme().to_string())),
+        Value::List(items) => ("list", Json::Array(items.iter().map(value_to_json).collect())),
+        Value::Nil => ("nil", Json::Null),
+    };
+    Json::object(vec![("kind", Json::String(kind.to_string())), ("value", value)])
+}
+
+fn node(kind: &str, span: Option<&Token>, mut fields: Vec<(&str, Json)>) -> Json {
+    let span = span.map_or(Json::Null, |token| Json::object(vec![
+        ("line", Json::Integer(token.get_line() as i64)),
+        ("column", Json::Integer(token.get_column() as i64)),
+    ]));
+    fields.insert(0, ("kind", Json::String(kind.to_string())));
+    fields.insert(1, ("span", span));
+    Json::object(fields)
+}
+
+fn exprs_to_json(exprs: &[Expr]) -> Json {
+    Json::Array(exprs.iter().map(expr_to_json).collect())
+}
+
+fn expr_to_json(expr: &Expr) -> Json {
+    let span = expr.first_token();
+    match expr {
+        Expr::Literal(value) => node("Literal", span, vec![("value", value_to_json(value))]),
+        Expr::Unary { operator, right } => node("Unary", span, vec![
+            ("operator", token_to_json(operator)),
+            ("right", expr_to_json(right)),
+        ]),
+        Expr::Binary { left, operator, right } => node("Binary", span, vec![
+            ("left", expr_to_json(left)),
+            ("operator", token_to_json(operator)),
+            ("right", expr_to_json(right)),
+        ]),
+        Expr::Grouping(expr) => node("Grouping", span, vec![("expression", expr_to_json(expr))]),
+        Expr::Interpolation(parts) => node("Interpolation", span, vec![("parts", exprs_to_json(parts))]),
+        Expr::Call { callee, paren, arguments } => node("Call", span, vec![
+            ("callee", expr_to_json(callee)),
+            ("paren", token_to_json(paren)),
+            ("arguments", exprs_to_json(arguments)),
+        ]),
+        Expr::Index { object, bracket, index } => node("Index", span, vec![
+            ("object", expr_to_json(object)),
+            ("bracket", token_to_json(bracket)),
+            ("index", expr_to_json(index)),
+        ]),
+        Expr::Variable(name) => node("Variable", span, vec![("name", token_to_json(name))]),
+        Expr::Assign { name, value } => node("Assign", span, vec![
+            ("name", token_to_json(name)),
+            ("value", expr_to_json(value)),
+        ]),
+        Expr::CompoundAssign { name, operator, value } => node("CompoundAssign", span, vec![
+            ("name", token_to_json(name)),
+            ("operator", token_to_json(operator)),
+            ("value", expr_to_json(value)),
+        ]),
+        Expr::Increment { name, operator, prefix } => node("Increment", span, vec![
+            ("name", token_to_json(name)),
+            ("operator", token_to_json(operator)),
+            ("prefix", Json::Bool(*prefix)),
+        ]),
+        Expr::IndexAssign { object, bracket, index, operator, value } => node("IndexAssign", span, vec![
+            ("object", expr_to_json(object)),
+            ("bracket", token_to_json(bracket)),
+            ("index", expr_to_json(index)),
+            ("operator", token_to_json(operator)),
+            ("value", expr_to_json(value)),
+        ]),
+        Expr::IndexIncrement { object, bracket, index, operator, prefix } => node("IndexIncrement", span, vec![
+            ("object", expr_to_json(object)),
+            ("bracket", token_to_json(bracket)),
+            ("index", expr_to_json(index)),
+            ("operator", token_to_json(operator)),
+            ("prefix", Json::Bool(*prefix)),
+        ]),
+    }
+}
+
+fn stmt_to_json(stmt: &Stmt) -> Json {
+    let span = stmt.first_token();
+    match stmt {
+        Stmt::Expr(expr) => node("Expr", span, vec![("expression", expr_to_json(expr))]),
+        Stmt::Print(keyword, expr) => node("Print", span, vec![
+            ("keyword", token_to_json(keyword)),
+            ("expression", expr_to_json(expr)),
+        ]),
+        Stmt::Var(name, initializer) => node("Var", span, vec![
+            ("name", token_to_json(name)),
+            ("initializer", initializer.as_ref().map_or(Json::Null, expr_to_json)),
+        ]),
+        Stmt::Block(statements) => {
+            node("Block", span, vec![("statements", Json::Array(statements.iter().map(stmt_to_json).collect()))])
+        },
+        Stmt::If { keyword, condition, then_branch, else_branch } => node("If", span, vec![
+            ("keyword", token_to_json(keyword)),
+            ("condition", expr_to_json(condition)),
+            ("then_branch", stmt_to_json(then_branch)),
+            ("else_branch", else_branch.as_deref().map_or(Json::Null, stmt_to_json)),
+        ]),
+        Stmt::While { keyword, condition, body } => node("While", span, vec![
+            ("keyword", token_to_json(keyword)),
+            ("condition", expr_to_json(condition)),
+            ("body", stmt_to_json(body)),
+        ]),
+        Stmt::Function(declaration) => node("Function", span, vec![
+            ("name", token_to_json(&declaration.name)),
+            ("params", Json::Array(declaration.params.iter().map(token_to_json).collect())),
+            ("body", Json::Array(declaration.body.iter().map(stmt_to_json).collect())),
+        ]),
+        Stmt::Return { keyword, value } => node("Return", span, vec![
+            ("keyword", token_to_json(keyword)),
+            ("value", value.as_ref().map_or(Json::Null, expr_to_json)),
+        ]),
+    }
+}
+
+fn field<'a>(json: &'a Json, name: &str) -> Result<&'a Json, String> {
+    json.get(name).ok_or_else(|| format!("Missing field '{}'.", name))
+}
+
+fn string<'a>(json: &'a Json, name: &str) -> Result<&'a str, String> {
+    field(json, name)?.as_str().ok_or_else(|| format!("Field '{}' must be a string.", name))
+}
+
+fn integer(json: &Json, name: &str) -> Result<i64, String> {
+    field(json, name)?.as_i64().ok_or_else(|| format!("Field '{}' must be an integer.", name))
+}
+
+fn array<'a>(json: &'a Json, name: &str) -> Result<&'a [Json], String> {
+    field(json, name)?.as_array().ok_or_else(|| format!("Field '{}' must be an array.", name))
+}
+
+fn boxed_expr(json: &Json, name: &str) -> Result<Box<Expr>, String> {
+    expr_from_json(field(json, name)?).map(Box::new)
+}
+
+fn token_field(json: &Json, name: &str) -> Result<Token, String> {
+    token_from_json(field(json, name)?)
+}
+
+// Print, if and while nodes gained a `keyword` after version 1 was published, so documents
+// written before then don't have one. The keyword is rebuilt at the node's span instead.
+fn keyword_field(json: &Json, t: TokenType, lexeme: &str) -> Result<Token, String> {
+    if json.get("keyword").is_some() {
+        return token_field(json, "keyword");
+    }
+    let span = json.get("span").filter(|span| **span != Json::Null);
+    let (line, column) = match span {
+        Some(span) => (integer(span, "line")? as usize, integer(span, "column")? as usize),
+        None => (0, 0),
+    };
+    Ok(Token::new(t, lexeme.to_string(), None, line, column))
+}
+
+fn token_from_json(json: &Json) -> Result<Token, String> {
+    let t = string(json, "type")?;
+    let t = TokenType::from_name(t).ok_or_else(|| format!("Unknown token type '{}'.", t))?;
+    let literal = match field(json, "literal")? {
+        Json::Null => None,
+        literal => Some(value_from_json(literal)?),
+    };
+    let line = integer(json, "line")? as usize;
+    let column = integer(json, "column")? as usize;
+    Ok(Token::new(t, string(json, "lexeme")?.to_string(), literal, line, column))
+}
+
+fn value_from_json(json: &Json) -> Result<Value, String> {
+    let value = field(json, "value")?;
+    let invalid = || format!("Invalid {} literal {}.", string(json, "kind").unwrap_or_default(), value);
+    match string(json, "kind")? {
+        "integer" => value.as_i64().map(Value::Integer).ok_or_else(invalid),
+        "number" => match value {
+            Json::Float(number) => Ok(Value::Number(*number)),
+            Json::Integer(integer) => Ok(Value::Number(*integer as f64)),
+            Json::String(text) => text.parse().map(Value::Number).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        },
+        "bigint" => {
+            let text = value.as_str().ok_or_else(invalid)?;
+            let parsed = match text.strip_prefix('-') {
+                Some(digits) => BigInt::from_str_radix(digits, 10).map(|value| -&value),
+                None => BigInt::from_str_radix(text, 10),
+            };
+            parsed.map(Value::BigInt).ok_or_else(invalid)
+        },
+        "string" => value.as_str().map(|string| Value::String(string.to_string())).ok_or_else(invalid),
+        "boolean" => value.as_bool().map(Value::Boolean).ok_or_else(invalid),
+        "list" => value.as_array().ok_or_else(invalid)?
+            .iter()
+            .map(value_from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|items| Value::List(Rc::new(items))),
+        "nil" => Ok(Value::Nil),
+        kind => Err(format!("Literals of kind '{}' cannot be imported.", kind)),
+    }
+}
+
+fn exprs_from_json(json: &Json, name: &str) -> Result<Vec<Expr>, String> {
+    array(json, name)?.iter().map(expr_from_json).collect()
+}
+
+fn expr_from_json(json: &Json) -> Result<Expr, String> {
+    Ok(match string(json, "kind")? {
+        "Literal" => Expr::Literal(value_from_json(field(json, "value")?)?),
+        "Unary" => Expr::Unary { operator: token_field(json, "operator")?, right: boxed_expr(json, "right")? },
+        "Binary" => Expr::Binary {
+            left: boxed_expr(json, "left")?,
+            operator: token_field(json, "operator")?,
+            right: boxed_expr(json, "right")?,
+        },
+        "Grouping" => Expr::Grouping(boxed_expr(json, "expression")?),
+        "Interpolation" => Expr::Interpolation(exprs_from_json(json, "parts")?),
+        "Call" => Expr::Call {
+            callee: boxed_expr(json, "callee")?,
+            paren: token_field(json, "paren")?,
+            arguments: exprs_from_json(json, "arguments")?,
+        },
+        "Index" => Expr::Index {
+            object: boxed_expr(json, "object")?,
+            bracket: token_field(json, "bracket")?,
+            index: boxed_expr(json, "index")?,
+        },
+        "Variable" => Expr::Variable(token_field(json, "name")?),
+        "Assign" => Expr::Assign { name: token_field(json, "name")?, value: boxed_expr(json, "value")? },
+        "CompoundAssign" => Expr::CompoundAssign {
+            name: token_field(json, "name")?,
+            operator: token_field(json, "operator")?,
+            value: boxed_expr(json, "value")?,
+        },
+        "Increment" => Expr::Increment {
+            name: token_field(json, "name")?,
+            operator: token_field(json, "operator")?,
+            prefix: field(json, "prefix")?.as_bool().ok_or("Field 'prefix' must be a boolean.")?,
+        },
+        "IndexAssign" => Expr::IndexAssign {
+            object: boxed_expr(json, "object")?,
+            bracket: token_field(json, "bracket")?,
+            index: boxed_expr(json, "index")?,
+            operator: token_field(json, "operator")?,
+            value: boxed_expr(json, "value")?,
+        },
+        "IndexIncrement" => Expr::IndexIncrement {
+            object: boxed_expr(json, "object")?,
+            bracket: token_field(json, "bracket")?,
+            index: boxed_expr(json, "index")?,
+            operator: token_field(json, "operator")?,
+            prefix: field(json, "prefix")?.as_bool().ok_or("Field 'prefix' must be a boolean.")?,
+        },
+        kind => return Err(format!("Unknown expression kind '{}'.", kind)),
+    })
+}
+
+fn stmts_from_json(json: &Json, name: &str) -> Result<Vec<Stmt>, String> {
+    array(json, name)?.iter().map(stmt_from_json).collect()
+}
+
+fn boxed_stmt(json: &Json, name: &str) -> Result<Box<Stmt>, String> {
+    stmt_from_json(field(json, name)?).map(Box::new)
+}
+
+fn stmt_from_json(json: &Json) -> Result<Stmt, String> {
+    Ok(match string(json, "kind")? {
+        "Expr" => Stmt::Expr(expr_from_json(field(json, "expression")?)?),
+        "Print" => Stmt::Print(keyword_field(json, TokenType::PRINT, "print")?, expr_from_json(field(json, "expression")?)?),
+        "Var" => {
+            let initializer = match field(json, "initializer")? {
+                Json::Null => None,
+                initializer => Some(expr_from_json(initializer)?),
+            };
+            Stmt::Var(token_field(json, "name")?, initializer)
+        },
+        "Block" => Stmt::Block(stmts_from_json(json, "statements")?),
+        "If" => Stmt::If {
+            keyword: keyword_field(json, TokenType::IF, "if")?,
+            condition: expr_from_json(field(json, "condition")?)?,
+            then_branch: boxed_stmt(json, "then_branch")?,
+            else_branch: match field(json, "else_branch")? {
+                Json::Null => None,
+                else_branch => Some(Box::new(stmt_from_json(else_branch)?)),
+            },
+        },
+        "While" => Stmt::While {
+            keyword: keyword_field(json, TokenType::WHILE, "while")?,
+            condition: expr_from_json(field(json, "condition")?)?,
+            body: boxed_stmt(json, "body")?,
+        },
+        "Function" => Stmt::Function(Rc::new(FunctionDecl {
+            name: token_field(json, "name")?,
+            params: array(json, "params")?.iter().map(token_from_json).collect::<Result<_, _>>()?,
+            body: stmts_from_json(json, "body")?,
+        })),
+        "Return" => Stmt::Return {
+            keyword: token_field(json, "keyword")?,
+            value: match field(json, "value")? {
+                Json::Null => None,
+                value => Some(expr_from_json(value)?),
+            },
+        },
+        kind => return Err(format!("Unknown statement kind '{}'.", kind)),
+    })
+}
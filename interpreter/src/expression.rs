@@ -6,6 +6,41 @@ pub enum Expr {
     Unary { operator: Token, right: Box<Expr> },
     Binary { left: Box<Expr>, operator: Token, right: Box<Expr> },
     Grouping(Box<Expr>),
+    Interpolation(Vec<Expr>),
+    Call { callee: Box<Expr>, paren: Token, arguments: Vec<Expr> },
+    Index { object: Box<Expr>, bracket: Token, index: Box<Expr> },
     Variable(Token),
     Assign { name: Token, value: Box<Expr> },
-}
\ No newline at end of file
+    CompoundAssign { name: Token, operator: Token, value: Box<Expr> },
+    Increment { name: Token, operator: Token, prefix: bool },
+    /// `object[index] = value`, or a compound assignment to the element when `operator` is
+    /// `+=` and the like. `object` is a variable or an element of one.
+    IndexAssign { object: Box<Expr>, bracket: Token, index: Box<Expr>, operator: Token, value: Box<Expr> },
+    IndexIncrement { object: Box<Expr>, bracket: Token, index: Box<Expr>, operator: Token, prefix: bool },
+}
+impl Expr {
+    /// The earliest token the expression holds. Literals hold no tokens, so expressions made
+    /// only of literals have none.
+    pub fn first_token(&self) -> Option<&Token> {
+        match self {
+            Expr::Literal(_) => None,
+            Expr::Unary { operator, right } => earliest([Some(operator), right.first_token()]),
+            Expr::Binary { left, operator, right } => earliest([left.first_token(), Some(operator), right.first_token()]),
+            Expr::Grouping(expr) => expr.first_token(),
+            Expr::Interpolation(parts) => earliest(parts.iter().map(Expr::first_token)),
+            Expr::Call { callee, paren, .. } => earliest([callee.first_token(), Some(paren)]),
+            Expr::Index { object, bracket, index } => earliest([object.first_token(), Some(bracket), index.first_token()]),
+            Expr::Variable(name) => Some(name),
+            Expr::Assign { name, .. } => Some(name),
+            Expr::CompoundAssign { name, .. } => Some(name),
+            Expr::Increment { name, operator, .. } => earliest([Some(name), Some(operator)]),
+            Expr::IndexAssign { object, .. } => object.first_token(),
+            Expr::IndexIncrement { object, operator, .. } => earliest([object.first_token(), Some(operator)]),
+        }
+    }
+}
+
+// Nodes don't store their own position, so a node's position is that of the earliest token it holds.
+pub(crate) fn earliest<'a>(tokens: impl IntoIterator<Item = Option<&'a Token>>) -> Option<&'a Token> {
+    tokens.into_iter().flatten().min_by_key(|token| (token.get_line(), token.get_column()))
+}
@@ -0,0 +1,287 @@
+use crate::syntax::{self, SyntaxElement, SyntaxKind, SyntaxNode};
+use crate::token::Token;
+use crate::token::TokenType::*;
+
+const INDENT: &str = "    ";
+
+/// Pretty-prints a script in the canonical style: four-space indentation, braces on the same
+/// line, single spaces around binary and assignment operators, one statement per line and at
+/// most one blank line between statements. Comments are kept where they were written.
+/// Scripts with syntax errors are returned as the list of errors instead.
+pub fn format(src: String) -> Result<String, Vec<String>> {
+    let tree = syntax::parse(src);
+    if !tree.errors.is_empty() {
+        return Err(tree.errors);
+    }
+    let mut formatter = Formatter::default();
+    formatter.statements(&tree.root);
+    let mut out = formatter.out;
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// What separates the previous word from the next one. Requests only ever widen the gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+enum Gap {
+    #[default]
+    None,
+    Space,
+    Line,
+    BlankLine,
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+    gap: Gap,
+    // Newlines in the source since the last word, used to keep blank lines and own-line comments.
+    newlines: usize,
+    // Set once a statement has started, so that lines broken by comments get a continuation indent.
+    mid_statement: bool,
+}
+
+impl Formatter {
+    fn request(&mut self, gap: Gap) {
+        self.gap = self.gap.max(gap);
+    }
+
+    fn break_line(&mut self) {
+        self.mid_statement = false;
+        self.request(if self.newlines >= 2 { Gap::BlankLine } else { Gap::Line });
+    }
+
+    fn write(&mut self, text: &str) {
+        let gap = match self.gap {
+            _ if self.out.is_empty() => Gap::None,
+            Gap::BlankLine if self.out.ends_with('{') => Gap::Line,
+            gap => gap,
+        };
+        match gap {
+            Gap::None => {},
+            Gap::Space => self.out.push(' '),
+            Gap::Line | Gap::BlankLine => {
+                self.out.push_str(if gap == Gap::Line { "\n" } else { "\n\n" });
+                let depth = self.indent + self.mid_statement as usize;
+                self.out.push_str(&INDENT.repeat(depth));
+            },
+        }
+        self.out.push_str(text);
+        self.gap = Gap::None;
+        self.newlines = 0;
+        self.mid_statement = true;
+    }
+
+    fn token(&mut self, token: &Token) {
+        self.write(token.get_lexeme());
+    }
+
+    // Comments stay on their own line if they started one, and trail the previous word otherwise.
+    fn trivia(&mut self, token: &Token) {
+        let text = token.get_lexeme();
+        if token.get_type() == WHITESPACE {
+            self.newlines += text.matches('\n').count();
+            return;
+        }
+        match self.newlines {
+            0 => self.request(Gap::Space),
+            1 => self.request(Gap::Line),
+            _ => self.request(Gap::BlankLine),
+        }
+        // A comment doesn't start a statement, so one between statements doesn't indent the
+        // line after it as a continuation.
+        let mid_statement = self.mid_statement;
+        if text.starts_with("//") || text.starts_with("#!") {
+            self.write(text.trim_end());
+            self.request(Gap::Line);
+        } else {
+            self.write(text);
+            self.request(Gap::Space);
+        }
+        self.mid_statement = mid_statement;
+    }
+
+    // Calls `visit` with each child that isn't trivia, formatting the trivia in between.
+    fn walk(&mut self, node: &SyntaxNode, mut visit: impl FnMut(&mut Self, &SyntaxElement)) {
+        for child in &node.children {
+            match child {
+                SyntaxElement::Token(token) if syntax::is_trivia(token.get_type()) => self.trivia(token),
+                child => visit(self, child),
+            }
+        }
+    }
+
+    // The top level of a file or the inside of a block: one statement per line.
+    fn statements(&mut self, node: &SyntaxNode) {
+        self.walk(node, |formatter, child| match child {
+            SyntaxElement::Node(statement) => {
+                formatter.break_line();
+                formatter.statement(statement);
+                formatter.mid_statement = false;
+            },
+            SyntaxElement::Token(token) => match token.get_type() {
+                EOF => {},
+                LEFT_BRACE => {
+                    formatter.token(token);
+                    formatter.indent += 1;
+                    formatter.mid_statement = false;
+                },
+                RIGHT_BRACE => {
+                    formatter.indent -= 1;
+                    if !formatter.out.ends_with('{') {
+                        formatter.newlines = 0;
+                        formatter.break_line();
+                    }
+                    formatter.token(token);
+                },
+                _ => formatter.token(token),
+            },
+        });
+    }
+
+    fn statement(&mut self, node: &SyntaxNode) {
+        match node.kind {
+            SyntaxKind::Block => self.statements(node),
+            SyntaxKind::IfStmt | SyntaxKind::WhileStmt => self.conditional(node),
+            SyntaxKind::ForStmt => self.for_statement(node),
+            SyntaxKind::FunDecl => self.function_declaration(node),
+            _ => self.walk(node, |formatter, child| match child {
+                SyntaxElement::Node(expression) => {
+                    if node.kind != SyntaxKind::ExprStmt {
+                        formatter.request(Gap::Space);
+                    }
+                    formatter.expression(expression);
+                },
+                SyntaxElement::Token(token) => {
+                    if matches!(token.get_type(), IDENTIFIER | EQUAL) {
+                        formatter.request(Gap::Space);
+                    }
+                    formatter.token(token);
+                },
+            }),
+        }
+    }
+
+    // The body of an `if`, `else`, `while` or `for`: blocks open on the same line, single
+    // statements go on the next line, indented.
+    fn body(&mut self, node: &SyntaxNode) {
+        if node.kind == SyntaxKind::Block {
+            self.request(Gap::Space);
+            self.statement(node);
+        } else {
+            self.indent += 1;
+            self.newlines = 0;
+            self.break_line();
+            self.statement(node);
+            self.mid_statement = false;
+            self.indent -= 1;
+        }
+    }
+
+    fn conditional(&mut self, node: &SyntaxNode) {
+        let mut after_else = false;
+        let mut block_body = false;
+        self.walk(node, |formatter, child| match child {
+            SyntaxElement::Node(body) if body.kind == SyntaxKind::IfStmt && after_else => {
+                formatter.request(Gap::Space);
+                formatter.statement(body);
+            },
+            SyntaxElement::Node(body) if is_statement(body.kind) => {
+                block_body = body.kind == SyntaxKind::Block;
+                formatter.body(body);
+            },
+            SyntaxElement::Node(condition) => formatter.expression(condition),
+            SyntaxElement::Token(token) => {
+                match token.get_type() {
+                    LEFT_PAREN => formatter.request(Gap::Space),
+                    ELSE if block_body => formatter.request(Gap::Space),
+                    ELSE => {
+                        formatter.newlines = 0;
+                        formatter.break_line();
+                    },
+                    _ => {},
+                }
+                after_else = token.get_type() == ELSE;
+                formatter.token(token);
+            },
+        });
+    }
+
+    fn for_statement(&mut self, node: &SyntaxNode) {
+        let mut in_header = true;
+        self.walk(node, |formatter, child| match child {
+            SyntaxElement::Node(clause) if in_header && is_statement(clause.kind) => formatter.statement(clause),
+            SyntaxElement::Node(body) if !in_header => formatter.body(body),
+            SyntaxElement::Node(clause) => {
+                formatter.request(Gap::Space);
+                formatter.expression(clause);
+            },
+            SyntaxElement::Token(token) => {
+                if token.get_type() == LEFT_PAREN {
+                    formatter.request(Gap::Space);
+                }
+                in_header = token.get_type() != RIGHT_PAREN;
+                formatter.token(token);
+            },
+        });
+    }
+
+    fn function_declaration(&mut self, node: &SyntaxNode) {
+        let mut previous = EOF;
+        self.walk(node, |formatter, child| match child {
+            SyntaxElement::Node(body) => {
+                formatter.request(Gap::Space);
+                formatter.statement(body);
+            },
+            SyntaxElement::Token(token) => {
+                if previous == FUN {
+                    formatter.request(Gap::Space);
+                }
+                formatter.token(token);
+                if token.get_type() == COMMA {
+                    formatter.request(Gap::Space);
+                }
+                previous = token.get_type();
+            },
+        });
+    }
+
+    fn expression(&mut self, node: &SyntaxNode) {
+        let operator_spacing = matches!(node.kind, SyntaxKind::BinaryExpr | SyntaxKind::AssignExpr);
+        let mut after_minus = false;
+        self.walk(node, |formatter, child| match child {
+            SyntaxElement::Node(operand) => {
+                // Keep `- -x` from turning into the decrement operator.
+                if after_minus && first_token(operand).is_some_and(|token| token.get_lexeme().starts_with('-')) {
+                    formatter.request(Gap::Space);
+                }
+                formatter.expression(operand);
+            },
+            SyntaxElement::Token(token) => {
+                if operator_spacing {
+                    formatter.request(Gap::Space);
+                }
+                formatter.token(token);
+                if operator_spacing || token.get_type() == COMMA {
+                    formatter.request(Gap::Space);
+                }
+                after_minus = token.get_type() == MINUS;
+            },
+        });
+    }
+}
+
+fn is_statement(kind: SyntaxKind) -> bool {
+    matches!(kind, SyntaxKind::VarDecl | SyntaxKind::PrintStmt | SyntaxKind::ExprStmt | SyntaxKind::Block
+        | SyntaxKind::IfStmt | SyntaxKind::WhileStmt | SyntaxKind::ForStmt | SyntaxKind::FunDecl | SyntaxKind::ReturnStmt)
+}
+
+fn first_token(node: &SyntaxNode) -> Option<&Token> {
+    node.children.iter().find_map(|child| match child {
+        SyntaxElement::Token(token) if syntax::is_trivia(token.get_type()) => None,
+        SyntaxElement::Token(token) => Some(token),
+        SyntaxElement::Node(node) => first_token(node),
+    })
+}
@@ -1,92 +1,265 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
 use std::rc::Rc;
 use std::cell::RefCell;
+use crate::bigint::BigInt;
+use crate::debugger::{Action, Debugger};
 use crate::environment::Environment;
 use crate::expression::Expr;
+use crate::native;
 use crate::statement::Stmt;
-use crate::token::{Value, Token, TokenType};
+use crate::token::{Function, Value, Token, TokenType};
+
+const MAX_SAFE_INTEGER: i64 = 1 << 53;
+const MAX_CALL_DEPTH: usize = 255;
+
 pub struct Interpreter {
     environment: Rc<RefCell<Environment>>,
+    // Lox calls recurse on the Rust stack, so runaway recursion is stopped before it overflows.
+    call_depth: usize,
+    debugger: Option<Debugger>,
+    // Where `print` writes: stdout, unless a debug adapter needs it for the protocol.
+    output: Box<dyn Write>,
+}
+
+// Why a statement stopped before reaching its end: a `return` unwinding to the call it returns
+// from, or a runtime error unwinding to the top.
+enum Unwind {
+    Return(Value),
+    Error(String),
+}
+
+impl From<String> for Unwind {
+    fn from(error: String) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let mut globals = Environment::new();
+        native::define_globals(&mut globals);
         Self {
-            environment: Rc::new(RefCell::new(Environment::new())),
+            environment: Rc::new(RefCell::new(globals)),
+            call_depth: 0,
+            debugger: None,
+            output: Box::new(io::stdout()),
         }
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    /// Runs top-level statements, reporting runtime errors. An error abandons the rest of the
+    /// statement it happened in, but not the statements after it, unless a debugger stopped the
+    /// script. Returns whether none failed.
+    pub fn interpret(&mut self, statements: impl IntoIterator<Item = Stmt>) -> bool {
+        let mut succeeded = true;
         for stmt in statements {
-            self.execute(&stmt);
+            if let Err(error) = self.run(std::slice::from_ref(&stmt)) {
+                if self.stopped() {
+                    break;
+                }
+                eprintln!("Runtime error: {}", error);
+                succeeded = false;
+            }
         }
+        succeeded
     }
 
-    fn execute_block(&mut self, stmts: &[Stmt], new_env: Rc<RefCell<Environment>>) {
-        let previous = self.environment.clone();
-
-        for stmt in stmts {
-            self.execute(stmt);
+    /// Runs statements until one fails and returns its runtime error.
+    pub fn run(&mut self, statements: &[Stmt]) -> Result<(), String> {
+        for stmt in statements {
+            match self.execute(stmt) {
+                Ok(()) | Err(Unwind::Return(_)) => {},
+                Err(Unwind::Error(error)) => return Err(error),
+            }
         }
+        Ok(())
+    }
+
+    /// Attaches a debugger that gets control before each statement.
+    pub fn set_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    /// Whether an attached debugger has stopped the script, after which no statement runs.
+    pub fn stopped(&self) -> bool {
+        self.debugger.as_ref().is_some_and(Debugger::stopped)
+    }
+
+    /// Sends the output of `print` statements somewhere other than stdout.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// The innermost scope of the statement being run.
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        self.environment.clone()
+    }
+
+    /// Evaluates an expression as if it appeared in the given scope.
+    pub fn evaluate_in(&mut self, environment: Rc<RefCell<Environment>>, expr: &Expr) -> Result<Value, String> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result
+    }
+
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.environment.borrow_mut().define(name.to_string(), value);
+    }
+
+    /// The bindings of the current scope chain, innermost first.
+    pub fn scopes(&self) -> Vec<Vec<(String, Value)>> {
+        self.environment.borrow().scopes()
+    }
+
+    /// Names of the variables and natives currently in scope.
+    pub fn defined_names(&self) -> Vec<String> {
+        self.environment.borrow().names()
+    }
+
+    fn execute_block(&mut self, stmts: &[Stmt], new_env: Rc<RefCell<Environment>>) -> Result<(), Unwind> {
+        let previous = std::mem::replace(&mut self.environment, new_env);
+        let result = stmts.iter().try_for_each(|stmt| self.execute(stmt));
         self.environment = previous;
+        result
     }
 
-    fn execute(&mut self, stmt: &Stmt) {
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), Unwind> {
+        // Taken out while it has control, so that expressions it evaluates aren't debugged too.
+        if let Some(mut debugger) = self.debugger.take() {
+            match debugger.before_statement(self, stmt) {
+                Action::Run => self.debugger = Some(debugger),
+                Action::Detach => {},
+                // Kept, so that every statement still to come is abandoned too.
+                Action::Stop => {
+                    self.debugger = Some(debugger);
+                    return Err(Unwind::Error("Stopped by the debugger.".to_string()));
+                },
+            }
+        }
         match stmt {
             Stmt::Expr(expr) => {
-                let _ = self.evaluate(expr);
+                self.evaluate(expr)?;
             }
-            Stmt::Print(expr) => {
-                let value = self.evaluate(expr);
-                match value {
-                    Ok(value) => {
-                        println!("{}", self.stringify(value))
-                    },
-                    Err(error) => {
-                        eprintln!("Runtime error: {}", error);
-                    }
-                }
+            Stmt::Print(_, expr) => {
+                let value = self.evaluate(expr)?;
+                let line = format!("{}\n", self.stringify(value));
+                let _ = self.output.write_all(line.as_bytes());
             },
             Stmt::Var(name, value) => {
-                let value = if let Some(expr) = value {
-                    self.evaluate(expr).unwrap_or(Value::Nil)
-                } else {
-                    Value::Nil
+                let value = match value {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
                 };
                 self.environment.borrow_mut().define(name.get_lexeme().to_string(), value);
             },
             Stmt::Block(stmts) => {
                 let new_env = Rc::new(RefCell::new(Environment::enclose(self.environment.clone())));
-                self.execute_block(stmts, new_env);
-            },
-            Stmt::If {condition, then_branch, else_branch} => {
-                match self.evaluate(condition) {
-                    Ok(value) => {
-                        if self.is_truthy(&value) {
-                            self.execute(then_branch);
-                        } else if let Some(else_stmt) = else_branch {
-                            self.execute(else_stmt);
-                        }
-                    }
-                    Err(error) => {
-                        eprintln!("Runtime error in if statement: {}", error);
-                    }
+                self.execute_block(stmts, new_env)?;
+            },
+            Stmt::If {condition, then_branch, else_branch, ..} => {
+                let value = self.evaluate(condition)?;
+                if self.is_truthy(&value) {
+                    self.execute(then_branch)?;
+                } else if let Some(else_stmt) = else_branch {
+                    self.execute(else_stmt)?;
                 }
             },
-            Stmt::While {condition, body } => {
-                while let Ok(value) = self.evaluate(condition) {
+            Stmt::While {condition, body, .. } => {
+                loop {
+                    let value = self.evaluate(condition)?;
                     if !self.is_truthy(&value) {
                         break;
                     }
-                    self.execute(body);
+                    self.execute(body)?;
                 }
-            }
+            },
+            Stmt::Function(declaration) => {
+                let function = Function { declaration: declaration.clone(), closure: self.environment.clone() };
+                let name = declaration.name.get_lexeme().to_string();
+                self.environment.borrow_mut().define(name, Value::Function(Rc::new(function)));
+            },
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                return Err(Unwind::Return(value));
+            },
         }
+        Ok(())
     }
 
-    fn stringify(&self, value: Value) -> String {
+    /// Calls a native or Lox function with already evaluated arguments.
+    pub fn call(&mut self, callee: &Value, arguments: &[Value], line: usize) -> Result<Value, String> {
+        let arity = match callee {
+            Value::NativeFunction(native) => native.arity.clone(),
+            Value::Function(function) => function.declaration.params.len()..=function.declaration.params.len(),
+            _ => return Err(format!("Can only call functions at line {}.", line)),
+        };
+        if !arity.contains(&arguments.len()) {
+            let expected = match (arity.start(), arity.end()) {
+                (fewest, most) if fewest == most => fewest.to_string(),
+                (fewest, most) => format!("{} to {}", fewest, most),
+            };
+            return Err(format!("Expected {} arguments but got {} at line {}.", expected, arguments.len(), line));
+        }
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(format!("Stack overflow at line {}.", line));
+        }
+        self.call_depth += 1;
+        let result = match callee {
+            // Natives return bare messages, which are located at the call here.
+            Value::NativeFunction(native) => (native.function)(self, arguments, line)
+                .map_err(|error| format!("{} at line {}.", error.trim_end_matches('.'), line)),
+            Value::Function(function) => {
+                if let Some(debugger) = &mut self.debugger {
+                    debugger.enter_call(function.declaration.name.get_lexeme(), line, self.environment.clone());
+                }
+                let mut environment = Environment::enclose(function.closure.clone());
+                for (param, argument) in function.declaration.params.iter().zip(arguments) {
+                    environment.define(param.get_lexeme().to_string(), argument.clone());
+                }
+                let result = match self.execute_block(&function.declaration.body, Rc::new(RefCell::new(environment))) {
+                    Ok(()) => Ok(Value::Nil),
+                    Err(Unwind::Return(value)) => Ok(value),
+                    Err(Unwind::Error(error)) => Err(error),
+                };
+                if let Some(debugger) = &mut self.debugger {
+                    debugger.exit_call();
+                }
+                result
+            },
+            _ => unreachable!(),
+        };
+        self.call_depth -= 1;
+        result
+    }
+
+    pub fn stringify(&self, value: Value) -> String {
         match value {
+            Value::Integer(integer) => integer.to_string(),
+            // Integral floats keep a trailing `.0` so they can't be mistaken for integers.
+            Value::Number(number) if number.is_finite() && number.fract() == 0.0 => format!("{:.1}", number),
             Value::Number(number) => number.to_string(),
+            Value::BigInt(bigint) => bigint.to_string(),
             Value::Boolean(boolean) => boolean.to_string(),
+            Value::NativeFunction(native) => format!("<native fn {}>", native.name),
+            Value::Function(function) => format!("{:?}", function),
+            // Strings inside a list are quoted so that `["a, b"]` and `["a", "b"]` differ.
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| match item {
+                    Value::String(string) => format!("{:?}", string),
+                    item => self.stringify(item.clone()),
+                }).collect();
+                format!("[{}]", items.join(", "))
+            },
             Value::String(string) => string,
             Value::Nil => "nil".to_string(),
         }
@@ -100,76 +273,386 @@ impl Interpreter {
         }
     }
 
-    fn evaluate(&mut self, expr: &Expr) -> Result<Value, String> {
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, String> {
         match expr {
             Expr::Literal(value) => Ok(value.clone()),
             Expr::Unary { operator, right} => {
                 let right = self.evaluate(right)?;
                 match operator.get_type() {
                     TokenType::MINUS => match right {
+                        Value::Integer(value) => value.checked_neg().map(Value::Integer).ok_or_else(|| self.overflow(operator)),
                         Value::Number(value) => Ok(Value::Number(-value)),
-                        _ => Err(format!("Not a number: {:?}", operator)),
+                        Value::BigInt(value) => Ok(Value::BigInt(-&value)),
+                        _ => Err(format!("Operand of '-' must be a number at line {}.", operator.get_line())),
                     },
                     TokenType::BANG => Ok(Value::Boolean(!self.is_truthy(&right))),
-                    _ => Err(format!("Unknown unary operator: {:?}", operator)),
+                    TokenType::TILDE => Ok(Value::Integer(!self.integer_operand(operator, &right)?)),
+                    _ => Err(format!("Unknown unary operator '{}' at line {}.", operator.get_lexeme(), operator.get_line())),
                 }
             }
             Expr::Binary { operator, left, right } => {
-                let left = self.evaluate(&left)?;
-                let right = self.evaluate(&right)?;
-
-                match operator.get_type() {
-                    TokenType::PLUS => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left + right)),
-                        (Value::String(left), Value::String(right)) => Ok(Value::String(left + &right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    TokenType::MINUS => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left - right)),
-                        _ => Err(format!("Not a number or non-numeric values for operator: {:?}", operator)),
-                    },
-                    TokenType::STAR => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left * right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    TokenType::SLASH => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => {
-                            if right == 0.0 {
-                                Err("Division by zero not allowed.".to_string())
-                            } else {
-                                Ok(Value::Number(left / right))
-                            }
-                        }
-                        _ => Err(format!("Error {:?} not supported or types not numeric.", operator)),
-                    }
-                    TokenType::EQUAL_EQUAL => Ok(Value::Boolean(left == right)),
-                    TokenType::BANG_EQUAL => Ok(Value::Boolean(left != right)),
-                    TokenType::GREATER => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Boolean(left > right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    TokenType::GREATER_EQUAL => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Boolean(left >= right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    TokenType::LESS => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Boolean(left < right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    TokenType::LESS_EQUAL => match (left, right) {
-                        (Value::Number(left), Value::Number(right)) => Ok(Value::Boolean(left <= right)),
-                        _ => Err(format!("Error {:?} not supported or mismatching types.", operator)),
-                    },
-                    _ => Err(format!("Error {:?} unknown binary operator.", operator)),
-                }
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                self.binary(operator, left, right)
             },
             Expr::Grouping(expr) => self.evaluate(expr),
+            Expr::Interpolation(parts) => {
+                let mut string = String::new();
+                for part in parts {
+                    let value = self.evaluate(part)?;
+                    string.push_str(&self.stringify(value));
+                }
+                Ok(Value::String(string))
+            },
+            Expr::Call { callee, paren, arguments } => {
+                let callee = self.evaluate(callee)?;
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+                self.call(&callee, &values, paren.get_line())
+            },
+            Expr::Index { object, bracket, index } => {
+                let object = self.evaluate(object)?;
+                let index = self.index(bracket, index)?;
+                self.element(&object, index, bracket)
+            },
             Expr::Variable(name) => self.environment.borrow().get(name),
             Expr::Assign { name, value } => {
                 let value = self.evaluate(value)?;
                 self.environment.borrow_mut().assign(name, value.clone())?;
                 Ok(value)
-            }
+            },
+            Expr::CompoundAssign { name, operator, value } => {
+                let current = self.environment.borrow().get(name)?;
+                let value = self.evaluate(value)?;
+                let value = self.binary(operator, current, value)?;
+                self.environment.borrow_mut().assign(name, value.clone())?;
+                Ok(value)
+            },
+            Expr::Increment { name, operator, prefix } => {
+                let current = self.environment.borrow().get(name)?;
+                let value = self.binary(operator, current.clone(), Value::Integer(1))?;
+                self.environment.borrow_mut().assign(name, value.clone())?;
+                Ok(if *prefix { value } else { current })
+            },
+            Expr::IndexAssign { object, bracket, index, operator, value } => {
+                let (name, path) = self.element_path(object, bracket, index)?;
+                let value = if operator.get_type() == TokenType::EQUAL {
+                    self.evaluate(value)?
+                } else {
+                    let current = self.element_at(name, &path)?;
+                    let value = self.evaluate(value)?;
+                    self.binary(operator, current, value)?
+                };
+                self.store_element(name, &path, value.clone())?;
+                Ok(value)
+            },
+            Expr::IndexIncrement { object, bracket, index, operator, prefix } => {
+                let (name, path) = self.element_path(object, bracket, index)?;
+                let current = self.element_at(name, &path)?;
+                let value = self.binary(operator, current.clone(), Value::Integer(1))?;
+                self.store_element(name, &path, value.clone())?;
+                Ok(if *prefix { value } else { current })
+            },
         }
     }
-}
\ No newline at end of file
+
+    fn index(&mut self, bracket: &Token, index: &Expr) -> Result<i64, String> {
+        match self.evaluate(index)? {
+            Value::Integer(index) => Ok(index),
+            _ => Err(format!("Index must be an integer at line {}.", bracket.get_line())),
+        }
+    }
+
+    fn element(&self, object: &Value, index: i64, bracket: &Token) -> Result<Value, String> {
+        let (item, length) = match object {
+            Value::List(items) => {
+                (usize::try_from(index).ok().and_then(|i| items.get(i)).cloned(), items.len())
+            },
+            Value::String(string) => {
+                let item = usize::try_from(index).ok().and_then(|i| string.chars().nth(i));
+                (item.map(|c| Value::String(c.to_string())), string.chars().count())
+            },
+            _ => return Err(format!("Can only index lists and strings at line {}.", bracket.get_line())),
+        };
+        item.ok_or_else(|| format!("Index {} out of range for length {} at line {}.", index, length, bracket.get_line()))
+    }
+
+    // The variable an assigned element is reached from and the indexes leading to it, outermost
+    // first. Each index is evaluated once, so `a[f()] += 1` calls `f` once.
+    fn element_path<'a>(&mut self, object: &'a Expr, bracket: &'a Token, index: &Expr) -> Result<(&'a Token, ElementPath<'a>), String> {
+        let (name, mut path) = match object {
+            Expr::Variable(name) => (name, Vec::new()),
+            Expr::Index { object, bracket, index } => self.element_path(object, bracket, index)?,
+            _ => return Err(format!("Invalid assignment target at line {}.", bracket.get_line())),
+        };
+        path.push((self.index(bracket, index)?, bracket));
+        Ok((name, path))
+    }
+
+    fn element_at(&self, name: &Token, path: &[(i64, &Token)]) -> Result<Value, String> {
+        let mut value = self.environment.borrow().get(name)?;
+        for (index, bracket) in path {
+            value = self.element(&value, *index, bracket)?;
+        }
+        Ok(value)
+    }
+
+    // Lists are values, so the element is replaced in a copy of any list that is shared and the
+    // outermost list is stored back in its variable.
+    fn store_element(&mut self, name: &Token, path: &[(i64, &Token)], value: Value) -> Result<(), String> {
+        let mut list = self.environment.borrow().get(name)?;
+        // Dropping the variable's reference lets a list that isn't shared be updated in place.
+        self.environment.borrow_mut().assign(name, Value::Nil)?;
+        let stored = replace_element(&mut list, path, value);
+        self.environment.borrow_mut().assign(name, list)?;
+        stored
+    }
+
+    // Bitwise operators accept integers, and floats that are exactly representable integers.
+    fn integer_operand(&self, operator: &Token, value: &Value) -> Result<i64, String> {
+        match value {
+            Value::Integer(i) => Ok(*i),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER as f64 => Ok(*n as i64),
+            _ => Err(format!("Operand of '{}' must be an integer, or a float holding an integer within ±2^53, got {:?}.", operator.get_lexeme(), value)),
+        }
+    }
+
+    fn overflow(&self, operator: &Token) -> String {
+        format!("Integer overflow in '{}' at line {}.", operator.get_lexeme(), operator.get_line())
+    }
+
+    // Names the operator as written, so that `x -= "a"` reports `-=`.
+    fn operand_types(&self, operator: &Token) -> String {
+        let (lexeme, line) = (operator.get_lexeme(), operator.get_line());
+        match operator.get_type() {
+            TokenType::PLUS_PLUS | TokenType::MINUS_MINUS => format!("Operand of '{}' must be a number at line {}.", lexeme, line),
+            TokenType::PLUS | TokenType::PLUS_EQUAL => format!("Operands of '{}' must be two numbers or two strings at line {}.", lexeme, line),
+            _ => format!("Operands of '{}' must be numbers at line {}.", lexeme, line),
+        }
+    }
+
+    // Integer operands stay integers (erroring on overflow); any float operand promotes both to floats.
+    fn arithmetic(
+        &self,
+        operator: &Token,
+        left: Value,
+        right: Value,
+        integer_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<Value, String> {
+        match (&left, &right) {
+            (Value::Integer(left), Value::Integer(right)) => {
+                integer_op(*left, *right).map(Value::Integer).ok_or_else(|| self.overflow(operator))
+            },
+            _ => match (left.as_f64(), right.as_f64()) {
+                (Some(left), Some(right)) => Ok(Value::Number(float_op(left, right))),
+                _ => Err(self.operand_types(operator)),
+            },
+        }
+    }
+
+    // Yields `None` when a NaN is involved, which makes every comparison false.
+    fn compare(&self, operator: &Token, left: &Value, right: &Value) -> Result<Option<Ordering>, String> {
+        match (left, right) {
+            (Value::Integer(left), Value::Integer(right)) => Ok(Some(left.cmp(right))),
+            (Value::Integer(left), Value::Number(right)) => Ok(compare_integer_to_float(*left, *right)),
+            (Value::Number(left), Value::Integer(right)) => Ok(compare_integer_to_float(*right, *left).map(Ordering::reverse)),
+            _ => match (left.as_f64(), right.as_f64()) {
+                (Some(left), Some(right)) => Ok(left.partial_cmp(&right)),
+                _ => Err(self.operand_types(operator)),
+            },
+        }
+    }
+
+    // Integers are promoted to bigints losslessly, but floats must be converted explicitly.
+    fn bigint_operands(&self, operator: &Token, left: &Value, right: &Value) -> Result<Option<(BigInt, BigInt)>, String> {
+        let promote = |value: &Value| match value {
+            Value::BigInt(value) => Some(value.clone()),
+            Value::Integer(value) => Some(BigInt::from(*value)),
+            _ => None,
+        };
+        match (left, right) {
+            (Value::BigInt(_), _) | (_, Value::BigInt(_)) => match (promote(left), promote(right)) {
+                (Some(left), Some(right)) => Ok(Some((left, right))),
+                _ => Err(format!("Cannot mix bigint and {:?} in '{}'; convert with bigint() or number().", if promote(left).is_some() { right } else { left }, operator.get_lexeme())),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn bigint_binary(&self, operator: &Token, left: BigInt, right: BigInt) -> Result<Value, String> {
+        match operator.get_type() {
+            TokenType::PLUS | TokenType::PLUS_EQUAL | TokenType::PLUS_PLUS => Ok(Value::BigInt(&left + &right)),
+            TokenType::MINUS | TokenType::MINUS_EQUAL | TokenType::MINUS_MINUS => Ok(Value::BigInt(&left - &right)),
+            TokenType::STAR | TokenType::STAR_EQUAL => Ok(Value::BigInt(&left * &right)),
+            // Bigints have no fractional part, so `/` floors just like `~/`.
+            TokenType::SLASH | TokenType::SLASH_EQUAL | TokenType::TILDE_SLASH => left.div_mod_floor(&right)
+                .map(|(quotient, _)| Value::BigInt(quotient))
+                .ok_or_else(|| "Division by zero not allowed.".to_string()),
+            TokenType::PERCENT | TokenType::PERCENT_EQUAL => left.div_mod_floor(&right)
+                .map(|(_, remainder)| Value::BigInt(remainder))
+                .ok_or_else(|| "Modulo by zero not allowed.".to_string()),
+            TokenType::STAR_STAR => {
+                if right.is_negative() {
+                    return Err(format!("Bigint exponent must be non-negative at line {}.", operator.get_line()));
+                }
+                match right.to_i64().and_then(|right| u32::try_from(right).ok()) {
+                    Some(exponent) => Ok(Value::BigInt(left.pow(exponent))),
+                    None => Err(format!("Bigint exponent too large at line {}.", operator.get_line())),
+                }
+            },
+            TokenType::GREATER => Ok(Value::Boolean(left > right)),
+            TokenType::GREATER_EQUAL => Ok(Value::Boolean(left >= right)),
+            TokenType::LESS => Ok(Value::Boolean(left < right)),
+            TokenType::LESS_EQUAL => Ok(Value::Boolean(left <= right)),
+            _ => Err(format!("'{}' is not supported for bigint operands at line {}.", operator.get_lexeme(), operator.get_line())),
+        }
+    }
+
+    fn binary(&self, operator: &Token, left: Value, right: Value) -> Result<Value, String> {
+        if !matches!(operator.get_type(), TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL)
+            && let Some((left, right)) = self.bigint_operands(operator, &left, &right)? {
+            return self.bigint_binary(operator, left, right);
+        }
+        match operator.get_type() {
+            TokenType::PLUS | TokenType::PLUS_EQUAL | TokenType::PLUS_PLUS => match (left, right) {
+                (Value::String(left), Value::String(right)) => Ok(Value::String(left + &right)),
+                (left, right) => self.arithmetic(operator, left, right, i64::checked_add, |a, b| a + b),
+            },
+            TokenType::MINUS | TokenType::MINUS_EQUAL | TokenType::MINUS_MINUS => {
+                self.arithmetic(operator, left, right, i64::checked_sub, |a, b| a - b)
+            },
+            TokenType::STAR | TokenType::STAR_EQUAL => {
+                self.arithmetic(operator, left, right, i64::checked_mul, |a, b| a * b)
+            },
+            // `/` is always true division; `~/` is the integer-preserving one.
+            TokenType::SLASH | TokenType::SLASH_EQUAL => match (left.as_f64(), right.as_f64()) {
+                (Some(_), Some(0.0)) => Err("Division by zero not allowed.".to_string()),
+                (Some(left), Some(right)) => Ok(Value::Number(left / right)),
+                _ => Err(self.operand_types(operator)),
+            },
+            TokenType::PERCENT | TokenType::PERCENT_EQUAL => {
+                if right.as_f64() == Some(0.0) {
+                    return Err("Modulo by zero not allowed.".to_string());
+                }
+                // Floored modulo: the result takes the sign of the divisor.
+                self.arithmetic(operator, left, right, |left, right| {
+                    // `checked_rem` reports `i64::MIN % -1` as an overflow, though the result is 0.
+                    let remainder = if right == -1 { 0 } else { left.checked_rem(right)? };
+                    if remainder != 0 && (remainder < 0) != (right < 0) {
+                        Some(remainder + right)
+                    } else {
+                        Some(remainder)
+                    }
+                }, |left, right| {
+                    let remainder = left % right;
+                    if remainder != 0.0 && (remainder < 0.0) != (right < 0.0) {
+                        remainder + right
+                    } else {
+                        remainder
+                    }
+                })
+            },
+            TokenType::TILDE_SLASH => {
+                if right.as_f64() == Some(0.0) {
+                    return Err("Division by zero not allowed.".to_string());
+                }
+                self.arithmetic(operator, left, right, |left, right| {
+                    let quotient = left.checked_div(right)?;
+                    if left % right != 0 && (left < 0) != (right < 0) {
+                        Some(quotient - 1)
+                    } else {
+                        Some(quotient)
+                    }
+                }, |left, right| (left / right).floor())
+            },
+            TokenType::STAR_STAR => match (left, right) {
+                (Value::Integer(left), Value::Integer(right)) if right >= 0 => {
+                    u32::try_from(right).ok()
+                        .and_then(|right| left.checked_pow(right))
+                        .map(Value::Integer)
+                        .ok_or_else(|| self.overflow(operator))
+                },
+                // Negative integer exponents produce fractions, so they promote to floats.
+                (left, right) => match (left.as_f64(), right.as_f64()) {
+                    (Some(left), Some(right)) => Ok(Value::Number(left.powf(right))),
+                    _ => Err(self.operand_types(operator)),
+                },
+            },
+            TokenType::AMPERSAND => {
+                let (left, right) = (self.integer_operand(operator, &left)?, self.integer_operand(operator, &right)?);
+                Ok(Value::Integer(left & right))
+            },
+            TokenType::PIPE => {
+                let (left, right) = (self.integer_operand(operator, &left)?, self.integer_operand(operator, &right)?);
+                Ok(Value::Integer(left | right))
+            },
+            TokenType::CARET => {
+                let (left, right) = (self.integer_operand(operator, &left)?, self.integer_operand(operator, &right)?);
+                Ok(Value::Integer(left ^ right))
+            },
+            TokenType::LESS_LESS | TokenType::GREATER_GREATER => {
+                let (left, right) = (self.integer_operand(operator, &left)?, self.integer_operand(operator, &right)?);
+                if !(0..64).contains(&right) {
+                    return Err(format!("Shift amount {} out of range for '{}'.", right, operator.get_lexeme()));
+                }
+                if operator.get_type() == TokenType::GREATER_GREATER {
+                    return Ok(Value::Integer(left >> right));
+                }
+                let result = left << right;
+                if result >> right != left {
+                    return Err(self.overflow(operator));
+                }
+                Ok(Value::Integer(result))
+            },
+            TokenType::EQUAL_EQUAL => Ok(Value::Boolean(left == right)),
+            TokenType::BANG_EQUAL => Ok(Value::Boolean(left != right)),
+            TokenType::GREATER | TokenType::GREATER_EQUAL | TokenType::LESS | TokenType::LESS_EQUAL => {
+                let ordering = self.compare(operator, &left, &right)?;
+                Ok(Value::Boolean(match operator.get_type() {
+                    TokenType::GREATER => ordering == Some(Ordering::Greater),
+                    TokenType::GREATER_EQUAL => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                    TokenType::LESS => ordering == Some(Ordering::Less),
+                    _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                }))
+            },
+            _ => Err(format!("Unknown binary operator '{}' at line {}.", operator.get_lexeme(), operator.get_line())),
+        }
+    }
+}
+
+// The indexes leading to an element, each with the `]` token errors are reported at.
+type ElementPath<'a> = Vec<(i64, &'a Token)>;
+
+fn replace_element(target: &mut Value, path: &[(i64, &Token)], value: Value) -> Result<(), String> {
+    let Some(((index, bracket), rest)) = path.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    let Value::List(items) = target else {
+        return Err(format!("Can only assign to list elements at line {}.", bracket.get_line()));
+    };
+    let length = items.len();
+    let item = usize::try_from(*index).ok().and_then(|i| Rc::make_mut(items).get_mut(i))
+        .ok_or_else(|| format!("Index {} out of range for length {} at line {}.", index, length, bracket.get_line()))?;
+    replace_element(item, rest, value)
+}
+
+// Compares exactly, unlike converting the integer to a float, which rounds above 2^53. A float
+// in range is compared by its integer part, and its fraction breaks the tie.
+fn compare_integer_to_float(integer: i64, float: f64) -> Option<Ordering> {
+    const TWO_TO_63: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() {
+        return None;
+    }
+    if float >= TWO_TO_63 {
+        return Some(Ordering::Less);
+    }
+    if float < -TWO_TO_63 {
+        return Some(Ordering::Greater);
+    }
+    let whole = float.trunc();
+    Some(integer.cmp(&(whole as i64)).then_with(|| 0.0.partial_cmp(&(float - whole)).unwrap()))
+}
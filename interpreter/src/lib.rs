@@ -0,0 +1,19 @@
+pub mod token;
+pub mod scanner;
+pub mod parser;
+pub mod expression;
+pub mod interpreter;
+pub mod statement;
+pub mod environment;
+pub mod bigint;
+pub mod syntax;
+pub mod format;
+pub mod ast_printer;
+pub mod json;
+pub mod export;
+pub mod repl;
+pub mod line_editor;
+pub mod test_runner;
+pub mod debugger;
+pub mod dap;
+mod native;
@@ -0,0 +1,449 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use unicode_ident::is_xid_continue;
+
+const MAX_HISTORY: usize = 1000;
+
+pub enum ReadLine {
+    Line(String),
+    // Ctrl-C: the caller should drop whatever it has collected so far.
+    Interrupted,
+    Eof,
+}
+
+/// A minimal readline: emacs-style editing keys, history browsing with the arrow keys,
+/// Ctrl-R reverse search and Tab completion. Falls back to plain line reads when stdin is not
+/// a terminal or raw mode isn't supported on this platform.
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+impl LineEditor {
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = history_path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+            if let Some(path) = &history_path {
+                let _ = fs::write(path, history.join("\n") + "\n");
+            }
+        }
+        Self { history, history_path }
+    }
+
+    pub fn read_line(&mut self, prompt: &str, completions: &[String]) -> io::Result<ReadLine> {
+        let Some(_raw_mode) = raw::RawMode::enable() else {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                return Ok(ReadLine::Eof);
+            }
+            return Ok(ReadLine::Line(line.trim_end_matches(['\n', '\r']).to_string()));
+        };
+        Editor {
+            prompt,
+            history: &self.history,
+            completions,
+            buffer: Vec::new(),
+            cursor: 0,
+            history_index: self.history.len(),
+            draft: Vec::new(),
+        }.run()
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        // Piped input isn't worth remembering.
+        if !io::stdin().is_terminal() || line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        // Appending rather than rewriting keeps the entries of sessions running side by side.
+        if let Some(path) = &self.history_path {
+            let _ = OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Escape,
+    Unknown,
+}
+
+struct Editor<'a> {
+    prompt: &'a str,
+    history: &'a [String],
+    completions: &'a [String],
+    buffer: Vec<char>,
+    cursor: usize,
+    // The history entry being shown; `history.len()` is the line being typed, saved in `draft`.
+    history_index: usize,
+    draft: Vec<char>,
+}
+
+impl Editor<'_> {
+    fn run(&mut self) -> io::Result<ReadLine> {
+        let mut pending = None;
+        loop {
+            self.refresh()?;
+            let key = match pending.take() {
+                Some(key) => key,
+                None => read_key()?,
+            };
+            match key {
+                Key::Enter => {
+                    write_out("\r\n")?;
+                    return Ok(ReadLine::Line(self.buffer.iter().collect()));
+                },
+                Key::Ctrl('c') => {
+                    write_out("^C\r\n")?;
+                    return Ok(ReadLine::Interrupted);
+                },
+                Key::Ctrl('d') if self.buffer.is_empty() => {
+                    write_out("\r\n")?;
+                    return Ok(ReadLine::Eof);
+                },
+                Key::Ctrl('d') | Key::Delete if self.cursor < self.buffer.len() => {
+                    self.buffer.remove(self.cursor);
+                },
+                Key::Backspace | Key::Ctrl('h') if self.cursor > 0 => {
+                    self.cursor -= 1;
+                    self.buffer.remove(self.cursor);
+                },
+                Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+                Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+                Key::Home | Key::Ctrl('a') => self.cursor = 0,
+                Key::End | Key::Ctrl('e') => self.cursor = self.buffer.len(),
+                Key::Up | Key::Ctrl('p') => self.browse_history(-1),
+                Key::Down | Key::Ctrl('n') => self.browse_history(1),
+                Key::Ctrl('k') => self.buffer.truncate(self.cursor),
+                Key::Ctrl('u') => {
+                    self.buffer.drain(..self.cursor);
+                    self.cursor = 0;
+                },
+                Key::Ctrl('w') => {
+                    let mut start = self.cursor;
+                    while start > 0 && self.buffer[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    while start > 0 && !self.buffer[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    self.buffer.drain(start..self.cursor);
+                    self.cursor = start;
+                },
+                Key::Ctrl('l') => write_out("\x1b[H\x1b[2J")?,
+                Key::Ctrl('r') => pending = self.reverse_search()?,
+                Key::Tab => self.complete()?,
+                Key::Char(c) => {
+                    self.buffer.insert(self.cursor, c);
+                    self.cursor += 1;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn refresh(&self) -> io::Result<()> {
+        let line: String = self.buffer.iter().collect();
+        let column = self.prompt.chars().count() + self.cursor;
+        let mut out = format!("\r{}{}\x1b[K\r", self.prompt, line);
+        if column > 0 {
+            out.push_str(&format!("\x1b[{}C", column));
+        }
+        write_out(&out)
+    }
+
+    fn set_buffer(&mut self, line: &str) {
+        self.buffer = line.chars().collect();
+        self.cursor = self.buffer.len();
+    }
+
+    fn browse_history(&mut self, step: isize) {
+        let Some(index) = self.history_index.checked_add_signed(step).filter(|&i| i <= self.history.len()) else {
+            return;
+        };
+        if self.history_index == self.history.len() {
+            self.draft = self.buffer.clone();
+        }
+        self.history_index = index;
+        if index == self.history.len() {
+            self.buffer = self.draft.clone();
+            self.cursor = self.buffer.len();
+        } else {
+            let history = self.history;
+            self.set_buffer(&history[index]);
+        }
+    }
+
+    // The most recent history entry before `before` that contains `query`.
+    fn search(&self, query: &str, before: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        (0..before).rev().find(|&i| self.history[i].contains(query))
+    }
+
+    // Ctrl-R: each typed character narrows the search and Ctrl-R again steps to older matches.
+    // Any other key accepts the match into the buffer and is then handled as usual, so Enter
+    // runs it straight away. Ctrl-G or Ctrl-C leave the buffer as it was.
+    fn reverse_search(&mut self) -> io::Result<Option<Key>> {
+        let mut query = String::new();
+        let mut found: Option<usize> = None;
+        loop {
+            let shown = found.map_or("", |i| self.history[i].as_str());
+            write_out(&format!("\r(reverse-i-search)`{}': {}\x1b[K", query, shown))?;
+            match read_key()? {
+                Key::Char(c) => {
+                    query.push(c);
+                    found = self.search(&query, found.map_or(self.history.len(), |i| i + 1));
+                },
+                Key::Backspace | Key::Ctrl('h') => {
+                    query.pop();
+                    found = self.search(&query, self.history.len());
+                },
+                Key::Ctrl('r') => {
+                    if let Some(older) = found.and_then(|i| self.search(&query, i)) {
+                        found = Some(older);
+                    }
+                },
+                Key::Ctrl('g') | Key::Ctrl('c') => return Ok(None),
+                key => {
+                    if let Some(i) = found {
+                        self.history_index = i;
+                        let history = self.history;
+                        self.set_buffer(&history[i]);
+                    }
+                    return Ok(if key == Key::Escape { None } else { Some(key) });
+                },
+            }
+        }
+    }
+
+    // Completes the identifier before the cursor. A unique match is inserted in full, several
+    // matches are extended to their common prefix and listed when that adds nothing.
+    fn complete(&mut self) -> io::Result<()> {
+        let mut start = self.cursor;
+        while start > 0 && is_xid_continue(self.buffer[start - 1]) {
+            start -= 1;
+        }
+        let prefix: String = self.buffer[start..self.cursor].iter().collect();
+        if prefix.is_empty() {
+            for _ in 0..4 {
+                self.buffer.insert(self.cursor, ' ');
+                self.cursor += 1;
+            }
+            return Ok(());
+        }
+
+        let mut matches: Vec<&str> = self.completions.iter()
+            .map(String::as_str)
+            .filter(|candidate| candidate.starts_with(&prefix))
+            .collect();
+        matches.sort_unstable();
+        matches.dedup();
+        let Some(common) = common_prefix(&matches) else {
+            return write_out("\x07");
+        };
+        if common.len() > prefix.len() {
+            for c in common[prefix.len()..].chars() {
+                self.buffer.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+        } else if matches.len() > 1 {
+            write_out(&format!("\r\n{}\r\n", matches.join("  ")))?;
+        }
+        Ok(())
+    }
+}
+
+fn common_prefix<'a>(words: &[&'a str]) -> Option<&'a str> {
+    let (first, rest) = words.split_first()?;
+    let mut end = first.len();
+    for word in rest {
+        end = first[..end].char_indices()
+            .zip(word.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(end.min(word.len()), |((i, _), _)| i);
+    }
+    Some(&first[..end])
+}
+
+fn write_out(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+fn read_byte() -> io::Result<Option<u8>> {
+    let mut byte = [0u8];
+    match io::stdin().read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+fn read_key() -> io::Result<Key> {
+    let Some(byte) = read_byte()? else {
+        return Ok(Key::Ctrl('d'));
+    };
+    Ok(match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        127 => Key::Backspace,
+        27 => read_escape()?,
+        1..=26 => Key::Ctrl((b'a' + byte - 1) as char),
+        0x80.. => {
+            // The rest of a UTF-8 sequence, whose length the lead byte gives.
+            let length = byte.leading_ones() as usize;
+            let mut bytes = vec![byte];
+            for _ in 1..length.min(4) {
+                bytes.extend(read_byte()?);
+            }
+            std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()).map_or(Key::Unknown, Key::Char)
+        },
+        _ => Key::Char(byte as char),
+    })
+}
+
+// ANSI escape sequences for the arrow, Home, End and Delete keys, as `ESC [ x` or `ESC O x`.
+fn read_escape() -> io::Result<Key> {
+    if !matches!(read_byte()?, Some(b'[' | b'O')) {
+        return Ok(Key::Escape);
+    }
+    Ok(match read_byte()? {
+        Some(b'A') => Key::Up,
+        Some(b'B') => Key::Down,
+        Some(b'C') => Key::Right,
+        Some(b'D') => Key::Left,
+        Some(b'H') => Key::Home,
+        Some(b'F') => Key::End,
+        Some(digit @ b'0'..=b'9') => {
+            let mut code = vec![digit];
+            while let Some(byte) = read_byte()? {
+                if byte == b'~' || !byte.is_ascii_digit() {
+                    break;
+                }
+                code.push(byte);
+            }
+            match code.as_slice() {
+                b"1" | b"7" => Key::Home,
+                b"4" | b"8" => Key::End,
+                b"3" => Key::Delete,
+                _ => Key::Unknown,
+            }
+        },
+        _ => Key::Unknown,
+    })
+}
+
+// Raw terminal mode through termios, declared by hand since the crate doesn't depend on libc.
+// The struct layout and flag values are those of the generic Linux ABI.
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64",
+    target_arch = "arm", target_arch = "riscv64")))]
+mod raw {
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Termios {
+        c_iflag: u32,
+        c_oflag: u32,
+        c_cflag: u32,
+        c_lflag: u32,
+        c_line: u8,
+        c_cc: [u8; 32],
+        c_ispeed: u32,
+        c_ospeed: u32,
+    }
+
+    unsafe extern "C" {
+        fn isatty(fd: i32) -> i32;
+        fn tcgetattr(fd: i32, termios: *mut Termios) -> i32;
+        fn tcsetattr(fd: i32, optional_actions: i32, termios: *const Termios) -> i32;
+    }
+
+    const STDIN: i32 = 0;
+    const STDOUT: i32 = 1;
+    const TCSANOW: i32 = 0;
+    const BRKINT: u32 = 0o2;
+    const INPCK: u32 = 0o20;
+    const ISTRIP: u32 = 0o40;
+    const ICRNL: u32 = 0o400;
+    const IXON: u32 = 0o2000;
+    const ISIG: u32 = 0o1;
+    const ICANON: u32 = 0o2;
+    const ECHO: u32 = 0o10;
+    const IEXTEN: u32 = 0o100000;
+    const VTIME: usize = 5;
+    const VMIN: usize = 6;
+
+    /// Puts the terminal in raw mode until dropped. Typed-ahead input is kept.
+    pub struct RawMode {
+        original: Termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> Option<RawMode> {
+            // SAFETY: `Termios` matches the C layout, and the pointers are valid for the calls.
+            unsafe {
+                if isatty(STDIN) == 0 || isatty(STDOUT) == 0 {
+                    return None;
+                }
+                let mut original: Termios = std::mem::zeroed();
+                if tcgetattr(STDIN, &mut original) != 0 {
+                    return None;
+                }
+                let mut raw = original;
+                raw.c_iflag &= !(BRKINT | ICRNL | INPCK | ISTRIP | IXON);
+                raw.c_lflag &= !(ECHO | ICANON | IEXTEN | ISIG);
+                raw.c_cc[VMIN] = 1;
+                raw.c_cc[VTIME] = 0;
+                if tcsetattr(STDIN, TCSANOW, &raw) != 0 {
+                    return None;
+                }
+                Some(RawMode { original })
+            }
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            // SAFETY: restores the settings read in `enable`.
+            unsafe {
+                tcsetattr(STDIN, TCSANOW, &self.original);
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64",
+    target_arch = "arm", target_arch = "riscv64"))))]
+mod raw {
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<RawMode> {
+            None
+        }
+    }
+}
@@ -1,72 +1,215 @@
-mod token;
-mod scanner;
-mod parser;
-mod expression;
-mod interpreter;
-mod statement;
-mod environment;
-
 use std::env;
-use std::io;
-use std::io::{Read, Write};
-use std::fs::File;
-use crate::interpreter::Interpreter;
-use crate::parser::Parser;
-use crate::scanner::Scanner;
+use std::io::{self, Read};
+use std::rc::Rc;
+use std::fs;
+use std::path::Path;
+use std::process;
+use interpreter::ast_printer;
+use interpreter::dap;
+use interpreter::debugger::Debugger;
+use interpreter::export;
+use interpreter::format;
+use interpreter::json;
+use interpreter::interpreter::Interpreter;
+use interpreter::parser::Parser;
+use interpreter::repl;
+use interpreter::scanner::Scanner;
+use interpreter::test_runner;
+use interpreter::token::Value;
 
-fn run_file(path: &str) {
-    let mut contents = String::new();
-    let mut file = File::open(path).unwrap();
-    file.read_to_string(&mut contents).unwrap();
-    run(&contents).unwrap();
+// Exits with 66 if the script can't be read.
+fn read_script(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(66);
+        },
+    }
 }
 
-fn run_prompt() {
-    let mut interpreter = Interpreter::new();
+fn run_file(path: &str, script_args: &[String]) {
+    run(read_script(path), script_args);
+}
 
-    loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+// `-` reads the script from stdin, so it can sit at the end of a shell pipeline.
+fn run_stdin(script_args: &[String]) {
+    let mut contents = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut contents) {
+        eprintln!("-: {}", err);
+        process::exit(66);
+    }
+    run(contents, script_args);
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-        if input == "quit" || input == "exit" {
-            break;
-        }
-        if let Err(err) = run_line(input, &mut interpreter) {
-            eprintln!("Error: {}", err);
-        }
+// Prints the parsed statements of a script as S-expressions instead of running them.
+fn dump_ast(path: &str, as_json: bool) {
+    let contents = read_script(path);
+    let statements = Parser::new(Scanner::new(contents));
+    if as_json {
+        println!("{}", export::export_program(&statements.collect::<Vec<_>>()));
+        return;
+    }
+    for statement in statements {
+        println!("{}", ast_printer::print_stmt(&statement));
     }
 }
 
-fn run_line(src: &str, interpreter: &mut Interpreter) -> Result<(), String> {
-    let scanner = Scanner::new(src.to_string());
-    let tokens = scanner.scan_tokens();
-    let mut parser = Parser::new(tokens);
-    let statements = parser.parse();
+// Prints one token per line: position, type, lexeme and literal value.
+fn dump_tokens(path: &str, as_json: bool) {
+    let contents = read_script(path);
+    if as_json {
+        println!("{}", export::export_tokens(Scanner::new(contents)));
+        return;
+    }
+    for token in Scanner::new(contents) {
+        match token {
+            Ok(token) => {
+                let position = format!("{}:{}", token.get_line(), token.get_column());
+                let literal = token.get_literal().map(ast_printer::literal).unwrap_or_default();
+                let lexeme = format!("{:?}", token.get_lexeme());
+                let line = format!("{:<8} {:<16} {:<16} {}", position, format!("{:?}", token.get_type()), lexeme, literal);
+                println!("{}", line.trim_end());
+            },
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+}
 
-    interpreter.interpret(statements);
-    Ok(())
+// Runs a program exported by `--dump-ast --json`.
+fn run_json(path: &str) {
+    let contents = read_script(path);
+    match json::parse(&contents).and_then(|document| export::import_program(&document)) {
+        Ok(statements) => {
+            if !Interpreter::new().interpret(statements) {
+                process::exit(70);
+            }
+        },
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(65);
+        },
+    }
 }
 
+// Arguments after the script are passed to it as the `args` list.
+fn run(src: String, script_args: &[String]) {
+    execute(script_interpreter(script_args), src);
+}
 
-fn run(src: &str) -> Result<(), String> {
-    let scanner = Scanner::new(src.to_string());
-    let tokens = scanner.scan_tokens();
-    let mut parser = Parser::new(tokens);
+// The whole script is parsed before any of it runs, so that a syntax error anywhere stops it
+// running at all. Tokens are still pulled lazily, so only the statements are held in memory.
+// Exits with 65 if the script has syntax errors and 70 if any statement failed at runtime.
+fn execute(mut interpreter: Interpreter, src: String) {
+    let mut parser = Parser::new(Scanner::new(src));
     let statements = parser.parse();
+    if parser.had_error() {
+        process::exit(65);
+    }
+    if !interpreter.interpret(statements) {
+        process::exit(70);
+    }
+}
+
+fn script_interpreter(script_args: &[String]) -> Interpreter {
     let mut interpreter = Interpreter::new();
-    interpreter.interpret(statements);
-    Ok(())
+    let script_args = script_args.iter().map(|arg| Value::String(arg.clone())).collect();
+    interpreter.define_global("args", Value::List(Rc::new(script_args)));
+    interpreter
 }
 
-fn main() {
-    if env::args().len() > 2 {
-        eprintln!("Usage: {} [script]", env::args().next().unwrap());
-    } else if env::args().len() == 2 {
-        run_file(&env::args().nth(1).unwrap());
+// Runs a script under the debugger, which prompts before the first statement.
+fn debug_file(path: &str, script_args: &[String]) {
+    let contents = read_script(path);
+    let mut interpreter = script_interpreter(script_args);
+    interpreter.set_debugger(Debugger::new(&contents));
+    execute(interpreter, contents);
+}
+
+// Rewrites each file in the canonical style. With `--check`, files are left untouched and the
+// exit status is non-zero if any of them would change.
+fn run_fmt(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if paths.is_empty() {
+        eprintln!("Usage: {} fmt [--check] <script>...", env::args().next().unwrap());
+        process::exit(64);
+    }
+
+    let mut failed = false;
+    for path in paths {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                failed = true;
+                continue;
+            },
+        };
+        match format::format(contents.clone()) {
+            Ok(formatted) if formatted == contents => {},
+            Ok(_) if check => {
+                println!("Would reformat {}", path);
+                failed = true;
+            },
+            Ok(formatted) => {
+                if let Err(err) = fs::write(path, formatted) {
+                    eprintln!("{}: {}", path, err);
+                    failed = true;
+                }
+            },
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}: {}", path, error);
+                }
+                failed = true;
+            },
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+// Runs the expectation-annotated scripts under `dir`, each in a fresh copy of this interpreter,
+// or with `--unit`, the `test_` functions they declare.
+fn run_test(dir: &str, unit: bool) {
+    let passed = if unit {
+        test_runner::run_unit_tests(Path::new(dir))
     } else {
-        run_prompt();
+        test_runner::run_tests(Path::new(dir), &env::current_exe().unwrap())
+    };
+    if !passed {
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.as_slice() {
+        [_, command, rest @ ..] if command == "fmt" => run_fmt(rest),
+        [_, command, dir] if command == "test" => run_test(dir, false),
+        [_, command, flag, dir] if command == "test" && flag == "--unit" => run_test(dir, true),
+        [_, flag, path] if flag == "--dump-ast" => dump_ast(path, false),
+        [_, flag, path] if flag == "--dump-tokens" => dump_tokens(path, false),
+        [_, flag, json, path] if flag == "--dump-ast" && json == "--json" => dump_ast(path, true),
+        [_, flag, json, path] if flag == "--dump-tokens" && json == "--json" => dump_tokens(path, true),
+        [_, flag, path] if flag == "--run-json" => run_json(path),
+        [_, flag, path, script_args @ ..] if flag == "--debug" => debug_file(path, script_args),
+        [_, flag] if flag == "--dap" => dap::run_server(io::BufReader::new(io::stdin()), io::stdout()),
+        [_, flag, code, script_args @ ..] if flag == "-e" || flag == "--eval" => run(code.clone(), script_args),
+        [_, flag, script_args @ ..] if flag == "-" => run_stdin(script_args),
+        [_, path, script_args @ ..] if !path.starts_with('-') => run_file(path, script_args),
+        [_] => repl::run_prompt(),
+        _ => {
+            eprintln!("Usage: {} [script | -e <code> | -] [args...]", args[0]);
+            eprintln!("       {} --dump-ast [--json] <script>", args[0]);
+            eprintln!("       {} --dump-tokens [--json] <script>", args[0]);
+            eprintln!("       {} --run-json <file>", args[0]);
+            eprintln!("       {} --debug <script> [args...]", args[0]);
+            eprintln!("       {} --dap", args[0]);
+            eprintln!("       {} fmt [--check] <script>...", args[0]);
+            eprintln!("       {} test [--unit] <dir>", args[0]);
+        },
     }
 }
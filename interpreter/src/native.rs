@@ -0,0 +1,94 @@
+use crate::ast_printer;
+use crate::bigint::BigInt;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::token::{NativeFunction, Value};
+
+const NATIVES: &[NativeFunction] = &[
+    NativeFunction { name: "bigint", arity: 1..=1, function: bigint },
+    NativeFunction { name: "number", arity: 1..=1, function: number },
+    NativeFunction { name: "len", arity: 1..=1, function: len },
+    NativeFunction { name: "assert", arity: 1..=2, function: assert },
+    NativeFunction { name: "assertEqual", arity: 2..=2, function: assert_equal },
+    NativeFunction { name: "assertThrows", arity: 1..=1, function: assert_throws },
+];
+
+pub fn define_globals(environment: &mut Environment) {
+    for native in NATIVES {
+        environment.define(native.name.to_string(), Value::NativeFunction(native.clone()));
+    }
+}
+
+// Converts an integer, an integral float or a decimal string to a bigint.
+fn bigint(_: &mut Interpreter, arguments: &[Value], _: usize) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::BigInt(value) => Ok(Value::BigInt(value.clone())),
+        Value::Integer(value) => Ok(Value::BigInt(BigInt::from(*value))),
+        Value::Number(value) => BigInt::from_f64(*value)
+            .map(Value::BigInt)
+            .ok_or_else(|| format!("Cannot convert {} to bigint: not an integer.", value)),
+        Value::String(text) => {
+            let parsed = match text.trim().strip_prefix('-') {
+                Some(digits) => BigInt::from_str_radix(digits, 10).map(|value| -&value),
+                None => BigInt::from_str_radix(text.trim(), 10),
+            };
+            parsed.map(Value::BigInt).ok_or_else(|| format!("Cannot convert \"{}\" to bigint.", text))
+        },
+        other => Err(format!("Cannot convert {:?} to bigint.", other)),
+    }
+}
+
+// Converts back to the regular number types: an integer when the value fits, otherwise a float.
+fn number(_: &mut Interpreter, arguments: &[Value], _: usize) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::BigInt(value) => Ok(value.to_i64().map(Value::Integer).unwrap_or_else(|| Value::Number(value.to_f64()))),
+        value @ (Value::Integer(_) | Value::Number(_)) => Ok(value.clone()),
+        Value::String(text) => text.trim().parse::<i64>().map(Value::Integer)
+            .or_else(|_| text.trim().parse::<f64>().map(Value::Number))
+            .map_err(|_| format!("Cannot convert \"{}\" to number.", text)),
+        other => Err(format!("Cannot convert {:?} to number.", other)),
+    }
+}
+
+// The number of items in a list, or of characters in a string.
+fn len(_: &mut Interpreter, arguments: &[Value], _: usize) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::List(items) => Ok(Value::Integer(items.len() as i64)),
+        Value::String(text) => Ok(Value::Integer(text.chars().count() as i64)),
+        other => Err(format!("Cannot take the length of {:?}.", other)),
+    }
+}
+
+// Fails unless the condition is truthy, with the message if one is given.
+fn assert(interpreter: &mut Interpreter, arguments: &[Value], _: usize) -> Result<Value, String> {
+    match (&arguments[0], arguments.get(1)) {
+        (Value::Nil | Value::Boolean(false), Some(message)) => Err(format!("Assertion failed: {}", interpreter.stringify(message.clone()))),
+        (Value::Nil | Value::Boolean(false), None) => Err("Assertion failed.".to_string()),
+        _ => Ok(Value::Nil),
+    }
+}
+
+fn assert_equal(_: &mut Interpreter, arguments: &[Value], _: usize) -> Result<Value, String> {
+    let (actual, expected) = (&arguments[0], &arguments[1]);
+    if actual == expected {
+        return Ok(Value::Nil);
+    }
+    Err(format!("Expected {} but got {}.", ast_printer::literal(expected), ast_printer::literal(actual)))
+}
+
+// Calls a function that takes no arguments and fails unless it raises a runtime error, whose
+// message is returned so that it can be checked in turn.
+fn assert_throws(interpreter: &mut Interpreter, arguments: &[Value], line: usize) -> Result<Value, String> {
+    let takes_no_arguments = match &arguments[0] {
+        Value::Function(function) => function.declaration.params.is_empty(),
+        Value::NativeFunction(native) => native.arity.contains(&0),
+        _ => false,
+    };
+    if !takes_no_arguments {
+        return Err(format!("assertThrows expects a function without parameters, got {}.", ast_printer::literal(&arguments[0])));
+    }
+    match interpreter.call(&arguments[0], &[], line) {
+        Ok(value) => Err(format!("Expected a runtime error but the function returned {}.", ast_printer::literal(&value))),
+        Err(error) => Ok(Value::String(error)),
+    }
+}
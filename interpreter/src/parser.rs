@@ -1,57 +1,109 @@
 use crate::token::{Token, TokenType, Value};
 use std::boxed::Box;
 use crate::expression::Expr;
-use crate::expression::Expr::{Binary, Literal, Unary};
+use crate::expression::Expr::{Binary, Unary};
 use crate::token::TokenType::*;
-use crate::statement::Stmt;
-
-pub struct Parser {
-    tokens: Vec<Token>,
-    pos: usize,
+use crate::scanner::ScanError;
+use std::rc::Rc;
+use crate::statement::{FunctionDecl, Stmt};
+
+/// Recursive descent parser that pulls tokens from the scanner on demand, keeping only
+/// the current lookahead token and the one just consumed.
+pub struct Parser<I: Iterator<Item = Result<Token, ScanError>>> {
+    tokens: I,
+    current: Option<Token>,
+    previous: Option<Token>,
+    repl: bool,
+    // How many function bodies enclose the current token, to reject `return` outside of one.
+    function_depth: usize,
+    // Where scan and syntax errors go.
+    error_handler: Box<dyn FnMut(&str)>,
+    had_error: bool,
+    // Whether a token that failed to scan was dropped just before `current`. A syntax error at
+    // `current` is then most likely caused by the missing token and isn't reported again.
+    after_scan_error: bool,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, pos: 0 }
+impl<I: Iterator<Item = Result<Token, ScanError>>> Parser<I> {
+    pub fn new(tokens: I) -> Parser<I> {
+        Self::with_error_handler(tokens, |error| eprintln!("{}", error))
+    }
+
+    /// A parser that sends scan and syntax errors to `handler` instead of stderr.
+    pub fn with_error_handler(tokens: I, handler: impl FnMut(&str) + 'static) -> Parser<I> {
+        let mut parser = Parser {
+            tokens,
+            current: None,
+            previous: None,
+            repl: false,
+            function_depth: 0,
+            error_handler: Box::new(handler),
+            had_error: false,
+            after_scan_error: false,
+        };
+        parser.current = parser.next_token();
+        parser
+    }
+
+    /// A parser for interactive input, where an expression statement at the very end may
+    /// leave off its `;`.
+    pub fn repl(tokens: I) -> Parser<I> {
+        Self::new(tokens).interactive()
+    }
+
+    /// Parses interactive input like `Parser::repl`, keeping this parser's error handler.
+    pub fn interactive(mut self) -> Parser<I> {
+        self.repl = true;
+        self
+    }
+
+    /// Whether a scan or syntax error has been reported so far.
+    pub fn had_error(&self) -> bool {
+        self.had_error
+    }
+
+    fn report(&mut self, error: &str) {
+        self.had_error = true;
+        (self.error_handler)(error);
     }
 
     pub fn parse(&mut self) -> Vec<Stmt> {
-        let mut statements = Vec::new();
+        self.by_ref().collect()
+    }
 
-        while !self.is_at_end() {
-            match self.declaration() {
-                Ok(stmt) => {
-                    statements.push(stmt)
+    // Scan errors are reported as they are pulled, and the parser carries on with the next token.
+    fn next_token(&mut self) -> Option<Token> {
+        self.after_scan_error = false;
+        loop {
+            match self.tokens.next()? {
+                Ok(token) => return Some(token),
+                Err(error) => {
+                    self.report(&error.to_string());
+                    self.after_scan_error = true;
                 },
-                Err(e) => {
-                    eprintln!("Parsing error: {}", e);
-                    self.synchronize();
-                }
-            }
-            if self.check(TokenType::EOF) {
-                break;
             }
         }
-        statements
     }
 
+    // Reports a syntax error, unless a scan error already accounts for it, and skips to the
+    // next statement.
+    fn recover(&mut self, error: String) {
+        if !self.after_scan_error {
+            self.report(&format!("Parsing error: {}", error));
+        }
+        self.synchronize();
+    }
 
     fn is_at_end(&self) -> bool {
-        self.pos >= self.tokens.len()
+        self.current.is_none()
     }
 
     fn peek(&self) -> &Token {
-        if self.is_at_end() {
-            return self.tokens.last().unwrap();
-        }
-        &self.tokens[self.pos]
+        self.current.as_ref().or(self.previous.as_ref()).expect("Token stream is empty")
     }
 
     fn previous(&self) -> &Token {
-        if self.pos == 0 {
-            panic!("Previous token is empty");
-        }
-        &self.tokens[self.pos - 1]
+        self.previous.as_ref().expect("Previous token is empty")
     }
 
     fn check(&self, token_type: TokenType) -> bool {
@@ -63,7 +115,8 @@ impl Parser {
 
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
-            self.pos += 1;
+            let next = self.next_token();
+            self.previous = std::mem::replace(&mut self.current, next);
         }
         self.previous()
     }
@@ -87,8 +140,9 @@ impl Parser {
     }
 
     fn synchronize(&mut self) {
+        self.advance();
         while !self.is_at_end() {
-            if self.previous().get_type() == SEMICOLON {
+            if self.previous.as_ref().is_some_and(|token| token.get_type() == SEMICOLON) {
                 return;
             }
 
@@ -106,6 +160,7 @@ impl Parser {
     }
 
     fn if_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous().clone();
         self.consume(LEFT_PAREN, "Expected '(' after 'if'")?;
         let condition = *self.expression()?;
         self.consume(RIGHT_PAREN, "Expected ')' after 'if' condition")?;
@@ -114,16 +169,18 @@ impl Parser {
         let else_branch = if self.match_token_types(&[TokenType::ELSE]) {
             Some(Box::new(self.statement()?))
         } else { None };
-        return Ok(Stmt::If {condition, then_branch, else_branch});
+        Ok(Stmt::If {keyword, condition, then_branch, else_branch})
     }
 
     fn print_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
         self.consume(TokenType::SEMICOLON, "Expect ';' after value.")?;
-        Ok(Stmt::Print(*value))
+        Ok(Stmt::Print(keyword, *value))
     }
 
     fn for_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous().clone();
         self.consume(LEFT_PAREN, "Expected '(' after 'for'")?;
         let initializer = if self.match_token_types(&[TokenType::VAR]) {
             Some(self.var_declaration()?)
@@ -148,7 +205,7 @@ impl Parser {
         if let Some(increment) = increment {
             body = Box::new(Stmt::Block(vec![*body, Stmt::Expr(*increment)]));
         }
-        let while_loop = Stmt::While {condition: *condition, body};
+        let while_loop = Stmt::While {keyword, condition: *condition, body};
         if let Some(init) = initializer {
             return Ok(Stmt::Block(vec![init, while_loop]));
         }
@@ -156,16 +213,20 @@ impl Parser {
     }
 
     fn while_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous().clone();
         self.consume(LEFT_PAREN, "Expected '(' after 'while'")?;
         let condition = *self.expression()?;
         self.consume(RIGHT_PAREN, "Expected ')' after 'while' condition")?;
         let body = Box::new(self.statement()?);
 
-        return Ok(Stmt::While {condition, body});
+        Ok(Stmt::While {keyword, condition, body})
     }
 
     fn expression_statement(&mut self) -> Result<Stmt, String> {
         let expr = self.expression()?;
+        if self.repl && self.check(EOF) {
+            return Ok(Stmt::Expr(*expr));
+        }
         self.consume(TokenType::SEMICOLON, "Expect ';' after expression.")?;
         Ok(Stmt::Expr(*expr))
     }
@@ -173,11 +234,47 @@ impl Parser {
     fn declaration(&mut self) -> Result<Stmt, String> {
         if self.match_token_types(&[TokenType::VAR]) {
             self.var_declaration()
+        } else if self.match_token_types(&[TokenType::FUN]) {
+            self.function_declaration()
         } else {
             self.statement()
         }
     }
 
+    fn function_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect function name.")?.clone();
+        self.consume(LEFT_PAREN, "Expect '(' after function name.")?;
+        let mut params = Vec::new();
+        if !self.check(RIGHT_PAREN) {
+            loop {
+                params.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?.clone());
+                if !self.match_token_types(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(RIGHT_PAREN, "Expect ')' after parameters.")?;
+        self.consume(LEFT_BRACE, "Expect '{' before function body.")?;
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        Ok(Stmt::Function(Rc::new(FunctionDecl { name, params, body: body? })))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous().clone();
+        if self.function_depth == 0 {
+            return Err(format!("Can't return from top-level code at line {}", keyword.get_line()));
+        }
+        let value = if !self.check(TokenType::SEMICOLON) {
+            Some(*self.expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::SEMICOLON, "Expect ';' after return value.")?;
+        Ok(Stmt::Return { keyword, value })
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt, String> {
         let name = self.consume(TokenType::IDENTIFIER, "Expect variable name.")?.clone();
         let mut initializer: Option<Expr> = None;
@@ -190,15 +287,17 @@ impl Parser {
 
     fn statement(&mut self) -> Result<Stmt, String> {
         if self.match_token_types(&[TokenType::IF]) {
-            return self.if_statement();
+            self.if_statement()
         } else if self.match_token_types(&[TokenType::PRINT]) {
-            return self.print_statement()
+            self.print_statement()
         } else if self.match_token_types(&[TokenType::LEFT_BRACE]) {
-           return Ok(Stmt::Block(self.block()?))
+            Ok(Stmt::Block(self.block()?))
         } else if self.match_token_types(&[TokenType::WHILE]) {
-            return self.while_statement();
+            self.while_statement()
         } else if self.match_token_types(&[TokenType::FOR]) {
-            return self.for_statement();
+            self.for_statement()
+        } else if self.match_token_types(&[TokenType::RETURN]) {
+            self.return_statement()
         } else {
             self.expression_statement()
         }
@@ -206,9 +305,10 @@ impl Parser {
 
     fn block(&mut self) -> Result<Vec<Stmt>, String> {
         let mut statements = Vec::new();
-        while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
-            if let Ok(statement) = self.declaration() {
-                statements.push(statement);
+        while !self.check(TokenType::RIGHT_BRACE) && !self.check(TokenType::EOF) && !self.is_at_end() {
+            match self.declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(e) => self.recover(e),
             }
         }
 
@@ -227,10 +327,26 @@ impl Parser {
             let equals = self.previous().clone();
             let value = self.assignment()?;
 
-            if let Expr::Variable(name) = *expr {
-                 return Ok(Box::new(Expr::Assign{name, value}));
-            }
-            return Err(format!("Invalid assignment target at line {}", equals.get_line()));
+            return match *expr {
+                Expr::Variable(name) => Ok(Box::new(Expr::Assign{name, value})),
+                Expr::Index { object, bracket, index } if is_element_target(&object) => {
+                    Ok(Box::new(Expr::IndexAssign { object, bracket, index, operator: equals, value }))
+                },
+                _ => Err(format!("Invalid assignment target at line {}", equals.get_line())),
+            };
+        }
+
+        if self.match_token_types(&[PLUS_EQUAL, MINUS_EQUAL, STAR_EQUAL, SLASH_EQUAL, PERCENT_EQUAL]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+
+            return match *expr {
+                Expr::Variable(name) => Ok(Box::new(Expr::CompoundAssign { name, operator, value })),
+                Expr::Index { object, bracket, index } if is_element_target(&object) => {
+                    Ok(Box::new(Expr::IndexAssign { object, bracket, index, operator, value }))
+                },
+                _ => Err(format!("Invalid assignment target at line {}", operator.get_line())),
+            };
         }
         Ok(expr)
     }
@@ -251,9 +367,69 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Box<Expr>, String> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while self.match_token_types(&[GREATER, GREATER_EQUAL, LESS, LESS_EQUAL]) {
+            let operator = self.previous().clone();
+            let right = self.bit_or()?;
+            expr = Box::new(Binary {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
+
+    fn bit_or(&mut self) -> Result<Box<Expr>, String> {
+        let mut expr = self.bit_xor()?;
+
+        while self.match_token_types(&[PIPE]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Box::new(Binary {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> Result<Box<Expr>, String> {
+        let mut expr = self.bit_and()?;
+
+        while self.match_token_types(&[CARET]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Box::new(Binary {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Box<Expr>, String> {
+        let mut expr = self.shift()?;
+
+        while self.match_token_types(&[AMPERSAND]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Box::new(Binary {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Box<Expr>, String> {
+        let mut expr = self.term()?;
+
+        while self.match_token_types(&[LESS_LESS, GREATER_GREATER]) {
             let operator = self.previous().clone();
             let right = self.term()?;
             expr = Box::new(Binary {
@@ -282,7 +458,7 @@ impl Parser {
 
     fn factor(&mut self) -> Result<Box<Expr>, String> {
         let mut expr = self.unary()?;
-        while self.match_token_types(&[SLASH, STAR]) {
+        while self.match_token_types(&[SLASH, STAR, PERCENT, TILDE_SLASH]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
             expr = Box::new(Binary {
@@ -295,7 +471,7 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Box<Expr>, String> {
-        if self.match_token_types(&[BANG, MINUS]) {
+        if self.match_token_types(&[BANG, MINUS, TILDE]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
             return Ok(Box::new(Unary {
@@ -303,7 +479,74 @@ impl Parser {
                 right,
             }));
         }
-        Ok(self.primary()?)
+        self.power()
+    }
+
+    // `**` binds tighter than unary minus on its left, so `-2 ** 2` is `-(2 ** 2)`, but
+    // its right operand is a full unary expression, which also makes it right-associative.
+    fn power(&mut self) -> Result<Box<Expr>, String> {
+        let expr = self.increment()?;
+
+        if self.match_token_types(&[STAR_STAR]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Box::new(Binary {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+        Ok(expr)
+    }
+
+    // Prefix `++` and `--` take a variable or element rather than a unary expression, so
+    // `++x ** 2` is `(++x) ** 2`.
+    fn increment(&mut self) -> Result<Box<Expr>, String> {
+        if self.match_token_types(&[PLUS_PLUS, MINUS_MINUS]) {
+            let operator = self.previous().clone();
+            let target = self.call()?;
+            return increment(*target, operator, true);
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Box<Expr>, String> {
+        let expr = self.call()?;
+
+        if self.match_token_types(&[PLUS_PLUS, MINUS_MINUS]) {
+            let operator = self.previous().clone();
+
+            return increment(*expr, operator, false);
+        }
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> Result<Box<Expr>, String> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token_types(&[LEFT_BRACKET]) {
+                let index = self.expression()?;
+                let bracket = self.consume(RIGHT_BRACKET, "Expect ']' after index.")?.clone();
+                expr = Box::new(Expr::Index { object: expr, bracket, index });
+                continue;
+            }
+            if !self.match_token_types(&[LEFT_PAREN]) {
+                break;
+            }
+            let mut arguments = Vec::new();
+            if !self.check(RIGHT_PAREN) {
+                loop {
+                    arguments.push(*self.expression()?);
+                    if !self.match_token_types(&[COMMA]) {
+                        break;
+                    }
+                }
+            }
+            let paren = self.consume(RIGHT_PAREN, "Expect ')' after arguments.")?.clone();
+            expr = Box::new(Expr::Call { callee: expr, paren, arguments });
+        }
+        Ok(expr)
     }
 
     fn primary(&mut self) -> Result<Box<Expr>, String> {
@@ -318,8 +561,8 @@ impl Parser {
         }
         if self.match_token_types(&[TokenType::NUMBER]) {
             let token = self.previous().clone();
-            if let Some(Value::Number(n)) = token.literal {
-                return Ok(Box::new(Expr::Literal(Value::Number(n))));
+            if let Some(literal @ (Value::Integer(_) | Value::Number(_) | Value::BigInt(_))) = token.literal {
+                return Ok(Box::new(Expr::Literal(literal)));
             }
         }
         if self.match_token_types(&[TokenType::STRING]) {
@@ -328,6 +571,9 @@ impl Parser {
                 return Ok(Box::new(Expr::Literal(Value::String(s))));
             }
         }
+        if self.match_token_types(&[TokenType::INTERPOLATION]) {
+            return self.interpolation();
+        }
         if self.match_token_types(&[TokenType::LEFT_PAREN]) {
             let expr = self.expression()?;
             self.consume(TokenType::RIGHT_PAREN, "Expect ')' after expression.")?;
@@ -336,6 +582,60 @@ impl Parser {
         if self.match_token_types(&[TokenType::IDENTIFIER]) {
             return Ok(Box::new(Expr::Variable(self.previous().clone())));
         }
-        Err("Expected expression.".to_string())
+        Err(format!("Expected expression. at line {}", self.peek().get_line()))
+    }
+
+    // Alternating string segments and embedded expressions, ending with a plain STRING.
+    fn interpolation(&mut self) -> Result<Box<Expr>, String> {
+        let mut parts = Vec::new();
+        loop {
+            if let Some(Value::String(segment)) = self.previous().literal.clone()
+                && !segment.is_empty() {
+                parts.push(Expr::Literal(Value::String(segment)));
+            }
+            if self.previous().get_type() == TokenType::STRING {
+                break;
+            }
+            parts.push(*self.expression()?);
+            if !self.match_token_types(&[TokenType::INTERPOLATION]) {
+                self.consume(TokenType::STRING, "Expect end of string after interpolated expression.")?;
+            }
+        }
+        Ok(Box::new(Expr::Interpolation(parts)))
+    }
+}
+
+// Elements can be assigned to when the list they are in is held by a variable, possibly
+// inside other lists, since the updated list has to be stored back somewhere.
+fn is_element_target(object: &Expr) -> bool {
+    match object {
+        Expr::Variable(_) => true,
+        Expr::Index { object, .. } => is_element_target(object),
+        _ => false,
+    }
+}
+
+fn increment(target: Expr, operator: Token, prefix: bool) -> Result<Box<Expr>, String> {
+    match target {
+        Expr::Variable(name) => Ok(Box::new(Expr::Increment { name, operator, prefix })),
+        Expr::Index { object, bracket, index } if is_element_target(&object) => {
+            Ok(Box::new(Expr::IndexIncrement { object, bracket, index, operator, prefix }))
+        },
+        _ => Err(format!("Invalid increment target at line {}", operator.get_line())),
+    }
+}
+
+/// Yields statements as soon as they are parsed. Syntax errors are reported and skipped.
+impl<I: Iterator<Item = Result<Token, ScanError>>> Iterator for Parser<I> {
+    type Item = Stmt;
+
+    fn next(&mut self) -> Option<Stmt> {
+        while !self.is_at_end() && !self.check(TokenType::EOF) {
+            match self.declaration() {
+                Ok(stmt) => return Some(stmt),
+                Err(e) => self.recover(e),
+            }
+        }
+        None
     }
-}
\ No newline at end of file
+}
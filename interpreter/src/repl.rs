@@ -0,0 +1,196 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::ast_printer;
+use crate::expression::Expr;
+use crate::interpreter::Interpreter;
+use crate::line_editor::{LineEditor, ReadLine};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, KEYWORDS};
+use crate::statement::Stmt;
+use crate::token::{TokenType, Value};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+const HISTORY_FILE: &str = ".lox_history";
+
+const COMMANDS: &[(&str, &str)] = &[
+    (":env", "list the variables in scope and their values"),
+    (":load <file>", "run a script in this session"),
+    (":reset", "discard all variables and start over"),
+    (":ast <code>", "show the parse tree of an expression or statements"),
+    (":type <expr>", "show the type of an expression's value"),
+    (":time <code>", "run code and report how long it took"),
+    (":help", "show this list"),
+    (":quit", "leave the REPL"),
+];
+
+pub fn run_prompt() {
+    let mut interpreter = Interpreter::new();
+    let mut editor = LineEditor::new(env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE)));
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+        let line = match editor.read_line(prompt, &completions(&interpreter)) {
+            Ok(ReadLine::Line(line)) => line,
+            Ok(ReadLine::Interrupted) => {
+                input.clear();
+                continue;
+            },
+            Ok(ReadLine::Eof) | Err(_) => break,
+        };
+        editor.add_history(&line);
+        if input.is_empty() && (line.trim() == "quit" || line.trim() == "exit") {
+            break;
+        }
+        if input.is_empty() && line.trim_start().starts_with(':') {
+            if !run_command(line.trim(), &mut interpreter) {
+                break;
+            }
+            continue;
+        }
+        // A blank line gives up on an unfinished statement and lets its errors show.
+        let abandon = !input.is_empty() && line.trim().is_empty();
+        input.push_str(&line);
+        input.push('\n');
+        if !abandon && is_incomplete(&input) {
+            continue;
+        }
+        run_line(&input, &mut interpreter);
+        input.clear();
+    }
+}
+
+// Runs a `:command` line and returns false when the session should end.
+fn run_command(line: &str, interpreter: &mut Interpreter) -> bool {
+    let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let argument = argument.trim();
+    match command {
+        ":env" => {
+            let scopes = interpreter.scopes();
+            for (depth, bindings) in scopes.iter().enumerate() {
+                if scopes.len() > 1 {
+                    println!("# scope {}", scopes.len() - 1 - depth);
+                }
+                for (name, value) in bindings {
+                    println!("{} = {}", name, ast_printer::literal(value));
+                }
+            }
+        },
+        ":load" if argument.is_empty() => eprintln!("Usage: :load <file>"),
+        ":load" => match fs::read_to_string(argument) {
+            // Nothing runs unless the whole file parses.
+            Ok(contents) => {
+                let mut parser = Parser::new(Scanner::new(contents));
+                let statements = parser.parse();
+                if !parser.had_error() {
+                    interpreter.interpret(statements);
+                }
+            },
+            Err(err) => eprintln!("Could not read {}: {}", argument, err),
+        },
+        ":reset" => *interpreter = Interpreter::new(),
+        ":ast" => {
+            for stmt in Parser::repl(Scanner::new(argument.to_string())) {
+                match stmt {
+                    Stmt::Expr(expr) => println!("{}", ast_printer::print_expr(&expr)),
+                    stmt => println!("{}", ast_printer::print_stmt(&stmt)),
+                }
+            }
+        },
+        ":type" => match Parser::repl(Scanner::new(argument.to_string())).next() {
+            Some(Stmt::Expr(expr)) => match interpreter.evaluate(&expr) {
+                Ok(value) => println!("{}", value.type_name()),
+                Err(error) => eprintln!("Runtime error: {}", error),
+            },
+            Some(_) => eprintln!("Usage: :type <expression>"),
+            None => {},
+        },
+        ":time" => {
+            let start = Instant::now();
+            run_line(argument, interpreter);
+            println!("Elapsed: {:?}", start.elapsed());
+        },
+        ":help" => {
+            for (usage, description) in COMMANDS {
+                println!("{:<14} {}", usage, description);
+            }
+        },
+        ":quit" | ":exit" => return false,
+        _ => eprintln!("Unknown command '{}'. Type :help for a list.", command),
+    }
+    true
+}
+
+// Tab completes keywords and any name defined so far.
+fn completions(interpreter: &Interpreter) -> Vec<String> {
+    let mut names = interpreter.defined_names();
+    names.extend(KEYWORDS.keys().map(|keyword| keyword.to_string()));
+    names
+}
+
+// Like Python's interactive mode, the value of an expression statement is echoed unless it
+// is nil or the expression was only run to assign a variable.
+fn run_line(src: &str, interpreter: &mut Interpreter) {
+    for stmt in Parser::repl(Scanner::new(src.to_string())) {
+        match stmt {
+            Stmt::Expr(expr) if !is_assignment(&expr) => match interpreter.evaluate(&expr) {
+                Ok(Value::Nil) => {},
+                Ok(value) => println!("{}", interpreter.stringify(value)),
+                Err(error) => eprintln!("Runtime error: {}", error),
+            },
+            stmt => {
+                interpreter.interpret([stmt]);
+            },
+        }
+    }
+}
+
+fn is_assignment(expr: &Expr) -> bool {
+    matches!(expr, Expr::Assign { .. } | Expr::CompoundAssign { .. } | Expr::Increment { .. }
+        | Expr::IndexAssign { .. } | Expr::IndexIncrement { .. })
+}
+
+/// Whether `src` stops partway through a statement, so that the REPL should keep reading:
+/// inside an unterminated string or block comment, with more brackets opened than closed, or
+/// without the `;` or `}` that ends a statement. A bare expression is complete once it ends in
+/// something that can finish an expression.
+pub fn is_incomplete(src: &str) -> bool {
+    let mut depth = 0i32;
+    let mut first = TokenType::EOF;
+    let mut last = TokenType::EOF;
+    for token in Scanner::new(src.to_string()) {
+        match token {
+            Ok(token) => {
+                let t = token.get_type();
+                if t == TokenType::EOF {
+                    break;
+                }
+                if depth == 0 && matches!(last, TokenType::SEMICOLON | TokenType::RIGHT_BRACE | TokenType::EOF) {
+                    first = t;
+                }
+                match t {
+                    TokenType::LEFT_PAREN | TokenType::LEFT_BRACE | TokenType::LEFT_BRACKET => depth += 1,
+                    TokenType::RIGHT_PAREN | TokenType::RIGHT_BRACE | TokenType::RIGHT_BRACKET => depth -= 1,
+                    _ => {},
+                }
+                last = t;
+            },
+            Err(error) if error.message.starts_with("Unterminated") => return true,
+            Err(_) => {},
+        }
+    }
+    if depth > 0 {
+        return true;
+    }
+    let ends_statement = matches!(last, TokenType::SEMICOLON | TokenType::RIGHT_BRACE | TokenType::EOF);
+    let ends_expression = matches!(last, TokenType::IDENTIFIER | TokenType::NUMBER | TokenType::STRING
+        | TokenType::TRUE | TokenType::FALSE | TokenType::NIL | TokenType::RIGHT_PAREN | TokenType::RIGHT_BRACKET
+        | TokenType::PLUS_PLUS | TokenType::MINUS_MINUS);
+    let starts_statement = matches!(first, TokenType::VAR | TokenType::PRINT | TokenType::IF
+        | TokenType::WHILE | TokenType::FOR | TokenType::LEFT_BRACE | TokenType::FUN | TokenType::RETURN);
+    let bare_expression = ends_expression && !starts_statement;
+    !ends_statement && !bare_expression
+}
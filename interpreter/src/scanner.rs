@@ -1,9 +1,12 @@
-use std::collections::{HashMap};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use once_cell::sync::Lazy;
+use unicode_ident::{is_xid_continue, is_xid_start};
+use crate::bigint::BigInt;
 use crate::token;
 use crate::token::{Token, TokenType};
 
-static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
+pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     let mut m = HashMap::new();
     m.insert("and", TokenType::AND);
     m.insert("class", TokenType::CLASS);
@@ -23,77 +26,317 @@ static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     m.insert("while", TokenType::WHILE);
     m
 });
+// Positions (`start`, `current`) are byte offsets into `src`, always on a char boundary,
+// so every cursor operation is constant time and token text is a plain slice.
 pub struct Scanner {
     src: String,
-    tokens: Vec<Token>,
+    // Tokens and errors produced by the last `scan_token` call, not yet handed out.
+    pending: VecDeque<Result<Token, ScanError>>,
+    finished: bool,
+    // In trivia mode every byte of the source ends up in some token: whitespace and comments
+    // get their own tokens, and text that failed to scan becomes an ERROR token.
+    trivia: bool,
+    emitted_until: usize,
     start: usize,
     current: usize,
     line: usize,
+    // Column of the char at `current`, counted in chars from 1.
+    column: usize,
+    start_line: usize,
+    start_column: usize,
+    // Every `${` currently being scanned, innermost last.
+    interpolations: Vec<Interpolation>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StringStyle {
+    // Raw strings keep backslashes and `${` as written.
+    raw: bool,
+    triple: Option<Triple>,
+}
+
+// Layout of a `"""` string body, as absolute source positions.
+#[derive(Debug, Clone, Copy)]
+struct Triple {
+    indent: usize,
+    content_end: usize,
+    body_end: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Interpolation {
+    // Unclosed `{` inside the embedded expression.
+    depth: usize,
+    // The string to resume once the expression's closing `}` is reached.
+    style: StringStyle,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}:{}] Error: {}", self.line, self.column, self.message)
+    }
 }
 
 impl Scanner {
     pub fn new(src: String) -> Self {
+        // A byte order mark is not part of the program text.
+        let current = if src.starts_with('\u{FEFF}') { '\u{FEFF}'.len_utf8() } else { 0 };
         Self {
             src,
-            tokens: Vec::new(),
-            start: 0,
-            current: 0,
+            pending: VecDeque::new(),
+            finished: false,
+            trivia: false,
+            emitted_until: current,
+            start: current,
+            current,
             line: 1,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
         }
     }
 
+    /// A scanner that keeps whitespace, comments and malformed text as tokens, so that
+    /// concatenating every lexeme reproduces the source exactly.
+    pub fn with_trivia(src: String) -> Self {
+        let mut scanner = Self::new(src);
+        scanner.trivia = true;
+        scanner.start = 0;
+        scanner.current = 0;
+        scanner.emitted_until = 0;
+        scanner
+    }
+
+    fn error(&mut self, message: String) {
+        self.error_at(message, self.start_line, self.start_column);
+    }
+
+    fn error_at(&mut self, message: String, line: usize, column: usize) {
+        self.pending.push_back(Err(ScanError { message, line, column }));
+    }
+
     fn is_at_end(&self) -> bool { self.current >= self.src.len() }
     fn advance(&mut self) -> char {
-        let c = self.src.chars().nth(self.current).unwrap_or('\0');
-        self.current += 1;
+        let Some(c) = self.src[self.current..].chars().next() else {
+            return '\0';
+        };
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         c
     }
 
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() || self.src.chars().nth(self.current).unwrap() != expected {
+        if self.is_at_end() || self.peek() != expected {
             return false;
         }
-        self.current += 1;
+        self.advance();
         true
     }
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        self.src.chars().nth(self.current).unwrap_or('\0')
+        self.src[self.current..].chars().next().unwrap_or('\0')
     }
 
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.src.len() {
-            '\0'
-        } else {
-            self.src.chars().nth(self.current + 1).unwrap()
-        }
+        let mut chars = self.src[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
     fn add_null_token(&mut self, t: TokenType) { self.add_token(t, None) }
     fn add_token(&mut self, t: TokenType, v: Option<token::Value>) {
         let text = self.src[self.start..self.current].to_string();
-        self.tokens.push(Token::new(t, text, v, self.line));
+        self.pending.push_back(Ok(Token::new(t, text, v, self.start_line, self.start_column)));
+        self.emitted_until = self.current;
+    }
+    fn add_trivia_token(&mut self, t: TokenType) {
+        if self.trivia {
+            self.add_null_token(t);
+        }
     }
 
-    fn string(&mut self) -> Result<(), String> {
-        while self.peek() != '\"' {
-            if self.peek() == '\n' { self.line += 1;}
-            self.advance();
+    // Scans a string literal, or the next segment of one when resuming after the `}` that
+    // closes an interpolated expression. A segment ending in `${` becomes an INTERPOLATION
+    // token, and scanning returns to ordinary tokens until the matching `}`.
+    fn string(&mut self, style: StringStyle) {
+        let (line, column) = (self.start_line, self.start_column);
+        let mut value = String::new();
+        let mut valid = true;
+        loop {
+            let closed = match style.triple {
+                Some(triple) => self.current >= triple.body_end,
+                None => self.peek() == '\"',
+            };
+            if closed || self.is_at_end() {
+                break;
+            }
+            if let Some(triple) = style.triple && self.current >= triple.content_end {
+                self.advance();
+                continue;
+            }
+            if !style.raw && self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+                self.interpolations.push(Interpolation { depth: 0, style });
+                if valid {
+                    self.add_token(TokenType::INTERPOLATION, Some(token::Value::String(value)));
+                }
+                return;
+            }
+            let (escape_line, column) = (self.line, self.column);
+            let c = self.advance();
+            if c != '\\' || style.raw {
+                value.push(c);
+                if let Some(triple) = style.triple && c == '\n' {
+                    self.skip_indentation(triple);
+                }
+                continue;
+            }
+            match self.escape() {
+                Ok(c) => value.push(c),
+                Err(message) => {
+                    self.error_at(message, escape_line, column);
+                    valid = false;
+                }
+            }
         }
         if self.is_at_end() {
-            return Err("Unterminated string.".to_string())
+            self.error_at("Unterminated string.".to_string(), line, column);
+            return;
         }
 
-        self.advance();
-        let lit = token::Value::String(self.src[self.start + 1..self.current - 1].to_string());
-        self.add_token(TokenType::STRING, Some(lit));
-        Ok(())
+        for _ in 0..if style.triple.is_some() { 3 } else { 1 } {
+            self.advance();
+        }
+        if valid {
+            self.add_token(TokenType::STRING, Some(token::Value::String(value)));
+        }
+    }
+
+    // Starts a `"""` string whose opening quotes have been consumed. Finds the closing quotes
+    // up front so the indentation common to every non-blank line can be stripped as we go.
+    // A line break right after the opening quotes and a blank last line are dropped too.
+    fn triple_string(&mut self, raw: bool) {
+        let rest = &self.src[self.current..];
+        let mut body_end = None;
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if !raw && c == '\\' {
+                chars.next();
+            } else if rest[i..].starts_with("\"\"\"") {
+                body_end = Some(i);
+                break;
+            }
+        }
+        let Some(body_end) = body_end else {
+            while !self.is_at_end() {
+                self.advance();
+            }
+            self.error("Unterminated string.".to_string());
+            return;
+        };
+
+        let body = &rest[..body_end];
+        let body_start = if body.starts_with('\n') {
+            1
+        } else if body.starts_with("\r\n") {
+            2
+        } else {
+            0
+        };
+        let is_blank = |line: &str| line.trim().is_empty();
+        let content_end = match body.rfind('\n') {
+            Some(newline) if is_blank(&body[newline + 1..]) => {
+                let line_end = if body[..newline].ends_with('\r') { newline - 1 } else { newline };
+                line_end.max(body_start)
+            },
+            _ => body_end,
+        };
+        let indent = body[body_start..content_end]
+            .split('\n')
+            .filter(|line| !is_blank(line))
+            .map(|line| line.chars().take_while(|&c| c == ' ' || c == '\t').count())
+            .min()
+            .unwrap_or(0);
+
+        let triple = Triple {
+            indent,
+            content_end: self.current + content_end,
+            body_end: self.current + body_end,
+        };
+        for _ in 0..body_start {
+            self.advance();
+        }
+        self.skip_indentation(triple);
+        self.string(StringStyle { raw, triple: Some(triple) });
+    }
+
+    fn skip_indentation(&mut self, triple: Triple) {
+        let mut skipped = 0;
+        while skipped < triple.indent && self.current < triple.content_end && matches!(self.peek(), ' ' | '\t') {
+            self.advance();
+            skipped += 1;
+        }
+    }
+
+    fn string_start(&mut self, raw: bool) {
+        if self.peek() == '\"' && self.peek_next() == '\"' {
+            self.advance();
+            self.advance();
+            self.triple_string(raw);
+        } else {
+            self.string(StringStyle { raw, triple: None });
+        }
+    }
+
+    // Decodes the escape sequence following a backslash.
+    fn escape(&mut self) -> Result<char, String> {
+        match self.advance() {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '\\' => Ok('\\'),
+            '\"' => Ok('\"'),
+            '0' => Ok('\0'),
+            '$' => Ok('$'),
+            'u' => {
+                if !self.match_char('{') {
+                    return Err("Expected '{' after '\\u'.".to_string());
+                }
+                let mut digits = String::new();
+                while self.peek().is_ascii_hexdigit() {
+                    digits.push(self.advance());
+                }
+                if !self.match_char('}') {
+                    return Err("Expected hex digits and '}' in '\\u{...}' escape.".to_string());
+                }
+                if digits.is_empty() || digits.len() > 6 {
+                    return Err(format!("Unicode escape '\\u{{{}}}' must have 1 to 6 hex digits.", digits));
+                }
+                u32::from_str_radix(&digits, 16).ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("Invalid Unicode scalar value '\\u{{{}}}'.", digits))
+            },
+            '\0' if self.is_at_end() => Err("Unterminated escape sequence.".to_string()),
+            '\n' => Err("Invalid escape sequence: a backslash can't end a line.".to_string()),
+            c => Err(format!("Invalid escape sequence '\\{}'.", c)),
+        }
     }
 
     fn identifier(&mut self) {
-        while self.peek().is_alphanumeric() || self.peek() == '_' {
+        while is_xid_continue(self.peek()) {
             self.advance();
         }
         let text = &self.src[self.start..self.current];
@@ -103,17 +346,103 @@ impl Scanner {
 
 
     fn number(&mut self) {
-        while self.peek().is_numeric() {
+        match self.number_literal() {
+            Ok(literal) => self.add_token(TokenType::NUMBER, Some(literal)),
+            Err(message) => {
+                // Swallow the rest of the malformed literal so it isn't rescanned as more tokens.
+                while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                    self.advance();
+                }
+                let literal = &self.src[self.start..self.current];
+                self.error(format!("{} in number literal '{}'.", message, literal));
+            }
+        }
+    }
+
+    fn number_literal(&mut self) -> Result<token::Value, String> {
+        let first = self.src[self.start..].chars().next().unwrap_or('0');
+        if first == '0' && matches!(self.peek(), 'x' | 'X' | 'b' | 'B' | 'o' | 'O') {
+            let radix = match self.advance() {
+                'x' | 'X' => 16,
+                'b' | 'B' => 2,
+                _ => 8,
+            };
+            let digits = self.digits(radix, String::new())?;
+            if self.peek().is_ascii_alphanumeric() && self.peek() != 'n' {
+                return Err(format!("Invalid digit '{}' for base {}", self.peek(), radix));
+            }
+            if digits.is_empty() {
+                return Err("Missing digits after radix prefix".to_string());
+            }
+            if self.match_char('n') {
+                return Ok(token::Value::BigInt(BigInt::from_str_radix(&digits, radix).unwrap()));
+            }
+            return i64::from_str_radix(&digits, radix)
+                .map(token::Value::Integer)
+                .map_err(|_| "Integer out of range (use an 'n' suffix for a bigint)".to_string());
+        }
+
+        let mut text = self.digits(10, first.to_string())?;
+        let mut is_float = false;
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            is_float = true;
             self.advance();
+            text.push('.');
+            text = self.digits(10, text)?;
         }
-        if self.peek() == '.' && self.peek_next().is_numeric() {
+        if matches!(self.peek(), 'e' | 'E') {
+            is_float = true;
             self.advance();
-            while self.peek().is_numeric() {
-                self.advance();
+            text.push('e');
+            if matches!(self.peek(), '+' | '-') {
+                text.push(self.advance());
+            }
+            if !self.peek().is_ascii_digit() {
+                return Err("Missing exponent digits".to_string());
+            }
+            text = self.digits(10, text)?;
+        }
+
+        if self.match_char('n') {
+            if is_float {
+                return Err("A bigint can't have a fraction or exponent".to_string());
+            }
+            return Ok(token::Value::BigInt(BigInt::from_str_radix(&text, 10).unwrap()));
+        }
+        if is_float {
+            return Ok(token::Value::Number(text.parse::<f64>().unwrap()));
+        }
+        text.parse::<i64>()
+            .map(token::Value::Integer)
+            .map_err(|_| "Integer out of range (use an 'n' suffix for a bigint)".to_string())
+    }
+
+    // Appends a run of digits in `radix` to `text`, dropping `_` separators, which are only
+    // allowed between two digits.
+    fn digits(&mut self, radix: u32, mut text: String) -> Result<String, String> {
+        let mut previous_digit = text.chars().last().is_some_and(|c| c.is_digit(radix));
+        let mut trailing_separator = false;
+        loop {
+            let c = self.peek();
+            if c == '_' {
+                if !previous_digit {
+                    return Err("Misplaced digit separator".to_string());
+                }
+                previous_digit = false;
+                trailing_separator = true;
+            } else if c.is_digit(radix) {
+                text.push(c);
+                previous_digit = true;
+                trailing_separator = false;
+            } else {
+                break;
             }
+            self.advance();
         }
-        let literal = token::Value::Number(self.src[self.start..self.current].parse::<f64>().unwrap());
-        self.add_token(TokenType::NUMBER, Some(literal));
+        if trailing_separator {
+            return Err("Trailing digit separator".to_string());
+        }
+        Ok(text)
     }
 
     fn scan_token(&mut self) {
@@ -121,47 +450,110 @@ impl Scanner {
         match c {
             '(' => self.add_null_token(TokenType::LEFT_PAREN),
             ')' => self.add_null_token(TokenType::RIGHT_PAREN),
-            '{' => self.add_null_token(TokenType::LEFT_BRACE),
-            '}' => self.add_null_token(TokenType::RIGHT_BRACE),
+            '[' => self.add_null_token(TokenType::LEFT_BRACKET),
+            ']' => self.add_null_token(TokenType::RIGHT_BRACKET),
+            // A `#!` line at the very start lets scripts be run directly on Unix.
+            '#' if self.peek() == '!' && self.src[..self.start].trim_start_matches('\u{FEFF}').is_empty() => {
+                while self.peek() != '\n' && !self.is_at_end() {
+                    self.advance();
+                }
+                self.add_trivia_token(TokenType::COMMENT);
+            },
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
+                }
+                self.add_null_token(TokenType::LEFT_BRACE)
+            },
+            '}' => match self.interpolations.last_mut() {
+                Some(Interpolation { depth: 0, style }) => {
+                    let style = *style;
+                    self.interpolations.pop();
+                    self.string(style);
+                },
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    self.add_null_token(TokenType::RIGHT_BRACE)
+                },
+                None => self.add_null_token(TokenType::RIGHT_BRACE),
+            },
             ',' => self.add_null_token(TokenType::COMMA),
             '.' => self.add_null_token(TokenType::DOT),
-            '-' => self.add_null_token(TokenType::MINUS),
-            '+' => self.add_null_token(TokenType::PLUS),
+            '-' => {
+                if self.match_char('-') {
+                    self.add_null_token(TokenType::MINUS_MINUS)
+                } else if self.match_char('=') {
+                    self.add_null_token(TokenType::MINUS_EQUAL)
+                } else {
+                    self.add_null_token(TokenType::MINUS)
+                }
+            },
+            '+' => {
+                if self.match_char('+') {
+                    self.add_null_token(TokenType::PLUS_PLUS)
+                } else if self.match_char('=') {
+                    self.add_null_token(TokenType::PLUS_EQUAL)
+                } else {
+                    self.add_null_token(TokenType::PLUS)
+                }
+            },
             ';' => self.add_null_token(TokenType::SEMICOLON),
             '/' => {
                 if self.match_char('/') {
-                    while !self.match_char('\n') {
-                        if self.match_char('\n') {
-                            self.line += 1;
-                            break;
-                        }
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
                     }
+                    self.add_trivia_token(TokenType::COMMENT);
                 } else if self.match_char('*') {
                     let mut depth = 1;
                     while depth > 0 {
                         if self.is_at_end() {
-                            eprintln!("Unterminated block comment");
+                            self.error("Unterminated block comment.".to_string());
                             return;
                         }
-                        if self.peek() == '\n' {
-                            self.line += 1;
-                        } else if self.peek() == '*' && self.peek_next() == '/' {
-                            self.advance();
+                        if self.peek() == '*' && self.peek_next() == '/' {
                             self.advance();
                             depth -= 1;
                         } else if self.peek() == '/' && self.peek_next() == '*' {
-                            self.advance();
                             self.advance();
                             depth += 1;
                         }
                         self.advance();
                     }
+                    self.add_trivia_token(TokenType::COMMENT);
+                } else if self.match_char('=') {
+                    self.add_null_token(TokenType::SLASH_EQUAL);
                 }
                 else {
                     self.add_null_token(TokenType::SLASH);
                 }
             },
-            '*' => self.add_null_token(TokenType::STAR),
+            '*' => {
+                if self.match_char('*') {
+                    self.add_null_token(TokenType::STAR_STAR)
+                } else if self.match_char('=') {
+                    self.add_null_token(TokenType::STAR_EQUAL)
+                } else {
+                    self.add_null_token(TokenType::STAR)
+                }
+            },
+            '%' => {
+                if self.match_char('=') {
+                    self.add_null_token(TokenType::PERCENT_EQUAL)
+                } else {
+                    self.add_null_token(TokenType::PERCENT)
+                }
+            },
+            '~' => {
+                if self.match_char('/') {
+                    self.add_null_token(TokenType::TILDE_SLASH)
+                } else {
+                    self.add_null_token(TokenType::TILDE)
+                }
+            },
+            '&' => self.add_null_token(TokenType::AMPERSAND),
+            '|' => self.add_null_token(TokenType::PIPE),
+            '^' => self.add_null_token(TokenType::CARET),
             '!' => {
                 if self.match_char('=') {
                     self.add_null_token(TokenType::BANG_EQUAL)
@@ -177,47 +569,102 @@ impl Scanner {
                 }
             },
             '<' => {
-                if self.match_char('=') {
+                if self.match_char('<') {
+                    self.add_null_token(TokenType::LESS_LESS)
+                } else if self.match_char('=') {
                     self.add_null_token(TokenType::LESS_EQUAL)
                 } else {
                     self.add_null_token(TokenType::LESS)
                 }
             },
             '>' => {
-                if self.match_char('=') {
+                if self.match_char('>') {
+                    self.add_null_token(TokenType::GREATER_GREATER)
+                } else if self.match_char('=') {
                     self.add_null_token(TokenType::GREATER_EQUAL)
                 } else {
                     self.add_null_token(TokenType::GREATER)
                 }
             },
-            ' ' | '\r' | '\t' => {},
-            '\n' => self.line += 1,
-            '\"' => {
-                match self.string() {
-                    Ok(_) => {},
-                    Err(e) => eprintln!("{}", e)
+            ' ' | '\r' | '\t' | '\n' => {
+                while matches!(self.peek(), ' ' | '\r' | '\t' | '\n') {
+                    self.advance();
                 }
+                self.add_trivia_token(TokenType::WHITESPACE);
+            },
+            '\u{FEFF}' if self.start == 0 => self.add_trivia_token(TokenType::WHITESPACE),
+            '\"' => {
+                self.string_start(false);
             },
-            'a'..='z' | 'A'..='Z' | '_' => {
-                self.start = self.current - 1;
-                self.identifier();
+            'r' if self.peek() == '\"' => {
+                self.advance();
+                self.string_start(true);
             },
-            '0'..='9' => {
-                self.start = self.current - 1;
-                self.number();
-            }
-            _ => { eprintln!("Unrecognized character: {}", c); return }
+            'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
+            '0'..='9' => self.number(),
+            c if is_xid_start(c) => self.identifier(),
+            _ => self.error(format!("Unrecognized character '{}'.", c)),
         }
     }
 
-    pub fn scan_tokens(mut self) -> Vec<Token> {
-        while !self.is_at_end() {
+    /// Scans the whole source up front, reporting any errors to stderr.
+    pub fn scan_tokens(self) -> Vec<Token> {
+        self.filter_map(|result| result.map_err(|error| eprintln!("{}", error)).ok()).collect()
+    }
+}
+
+/// Tokens are scanned lazily, one `scan_token` step at a time, and the stream always
+/// ends with a single EOF token.
+impl Iterator for Scanner {
+    type Item = Result<Token, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if self.finished {
+                return None;
+            }
+            if self.is_at_end() {
+                if !self.interpolations.is_empty() {
+                    self.error_at("Unterminated string interpolation.".to_string(), self.line, self.column);
+                }
+                self.pending.push_back(Ok(Token::new(TokenType::EOF, "".to_string(), None, self.line, self.column)));
+                self.finished = true;
+                continue;
+            }
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
             self.scan_token();
+            if self.trivia && self.emitted_until < self.current {
+                let text = self.src[self.emitted_until..self.current].to_string();
+                self.pending.push_back(Ok(Token::new(TokenType::ERROR, text, None, self.start_line, self.start_column)));
+                self.emitted_until = self.current;
+            }
         }
-        self.tokens.push(Token::new(TokenType::EOF, "".to_string(), None, self.line));
-        self.tokens
     }
-
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Scanning is linear as long as the cursor is a byte offset that only moves forward, so
+    // that each step reads the char at it instead of counting chars from the start.
+    #[test]
+    fn cursor_is_a_byte_offset_that_only_moves_forward() {
+        let src = "var s = \"ünïcödé ✓ ${1 + 2}\"; // — ✓\nprint s * 2.5e3;\n/* ∑ */ var 変数 = \"\"\"\n    ✓\n    \"\"\";\n".repeat(50);
+        for mut scanner in [Scanner::new(src.clone()), Scanner::with_trivia(src.clone())] {
+            let mut previous = scanner.current;
+            while let Some(token) = scanner.next() {
+                assert!(token.is_ok(), "{:?}", token);
+                assert!(scanner.current >= previous, "cursor moved back from {} to {}", previous, scanner.current);
+                assert!(src.is_char_boundary(scanner.current), "cursor at {} is inside a char", scanner.current);
+                previous = scanner.current;
+            }
+            assert_eq!(scanner.current, src.len());
+        }
+    }
+}
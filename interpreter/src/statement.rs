@@ -1,18 +1,54 @@
-use crate::expression::Expr;
+use std::rc::Rc;
+use crate::expression::{earliest, Expr};
 use crate::token::*;
 #[derive(Debug)]
 pub enum Stmt {
     Expr(Expr),
-    Print(Expr),
+    Print(Token, Expr),
     Var(Token, Option<Expr>),
     Block(Vec<Stmt>),
     If {
+        keyword: Token,
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
     While {
+        // The `for` keyword when the loop was written as one.
+        keyword: Token,
         condition: Expr,
         body: Box<Stmt>,
     },
-}
\ No newline at end of file
+    // Shared so that every closure created from the declaration can point at the same body.
+    Function(Rc<FunctionDecl>),
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+}
+
+#[derive(Debug)]
+pub struct FunctionDecl {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+}
+
+impl Stmt {
+    /// The earliest token the statement holds: its keyword or name where it has one.
+    pub fn first_token(&self) -> Option<&Token> {
+        match self {
+            Stmt::Expr(expr) => expr.first_token(),
+            Stmt::Print(keyword, _) => Some(keyword),
+            Stmt::Var(name, _) => Some(name),
+            Stmt::Block(statements) => earliest(statements.iter().map(Stmt::first_token)),
+            Stmt::If { keyword, .. } | Stmt::While { keyword, .. } | Stmt::Return { keyword, .. } => Some(keyword),
+            Stmt::Function(declaration) => Some(&declaration.name),
+        }
+    }
+
+    /// The line the statement starts on, if it holds any token.
+    pub fn line(&self) -> Option<usize> {
+        self.first_token().map(Token::get_line)
+    }
+}
@@ -0,0 +1,486 @@
+use crate::scanner::Scanner;
+use crate::token::Token;
+use crate::token::TokenType::{self, *};
+
+/// Concrete syntax tree node kinds. Unlike `Expr`/`Stmt`, the tree keeps every token of the
+/// source, including whitespace and comments, and `for` loops are not desugared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    SourceFile,
+    VarDecl,
+    FunDecl,
+    PrintStmt,
+    ExprStmt,
+    Block,
+    IfStmt,
+    WhileStmt,
+    ForStmt,
+    ReturnStmt,
+    LiteralExpr,
+    VariableExpr,
+    GroupingExpr,
+    UnaryExpr,
+    BinaryExpr,
+    AssignExpr,
+    IncrementExpr,
+    CallExpr,
+    IndexExpr,
+    InterpolationExpr,
+    // Tokens the parser could not fit into the grammar.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    /// The exact source text covered by this node.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.write_text(&mut text);
+        text
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.write_text(text),
+                SyntaxElement::Token(token) => text.push_str(token.get_lexeme()),
+            }
+        }
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    pub fn child_tokens(&self) -> impl Iterator<Item = &Token> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+}
+
+pub fn is_trivia(t: TokenType) -> bool {
+    matches!(t, WHITESPACE | COMMENT)
+}
+
+/// The result of a lossless parse: a tree that always covers the whole source, plus any
+/// scan and syntax errors found along the way.
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+    pub errors: Vec<String>,
+}
+
+pub fn parse(src: String) -> SyntaxTree {
+    let mut errors = Vec::new();
+    let tokens = Scanner::with_trivia(src)
+        .filter_map(|result| result.map_err(|error| errors.push(error.to_string())).ok())
+        .collect();
+    let mut builder = TreeBuilder { tokens, pos: 0, stack: Vec::new(), errors, function_depth: 0 };
+    builder.source_file();
+    let root = builder.stack.pop().map(|(kind, children)| SyntaxNode { kind, children }).unwrap();
+    SyntaxTree { root, errors: builder.errors }
+}
+
+// Binary operator precedence levels, loosest first. Mirrors `Parser` from `equality` down to `factor`.
+const BINARY_LEVELS: &[&[TokenType]] = &[
+    &[BANG_EQUAL, EQUAL_EQUAL],
+    &[GREATER, GREATER_EQUAL, LESS, LESS_EQUAL],
+    &[PIPE],
+    &[CARET],
+    &[AMPERSAND],
+    &[LESS_LESS, GREATER_GREATER],
+    &[MINUS, PLUS],
+    &[SLASH, STAR, PERCENT, TILDE_SLASH],
+];
+
+const ASSIGNMENT_OPERATORS: &[TokenType] = &[EQUAL, PLUS_EQUAL, MINUS_EQUAL, STAR_EQUAL, SLASH_EQUAL, PERCENT_EQUAL];
+
+// Recursive descent over the same grammar as `Parser`, building nodes on a stack. Trivia is
+// attached to whichever node is open when the next real token is consumed, and the parser
+// never fails: unexpected tokens are wrapped in `Error` nodes so that progress is guaranteed.
+struct TreeBuilder {
+    tokens: Vec<Token>,
+    pos: usize,
+    stack: Vec<(SyntaxKind, Vec<SyntaxElement>)>,
+    errors: Vec<String>,
+    function_depth: usize,
+}
+
+impl TreeBuilder {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.eat_trivia();
+        self.stack.push((kind, Vec::new()));
+    }
+
+    // Position in the open node that a later `start_node_at` can wrap from.
+    fn checkpoint(&mut self) -> usize {
+        self.eat_trivia();
+        self.stack.last().unwrap().1.len()
+    }
+
+    fn start_node_at(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let children = self.stack.last_mut().unwrap().1.split_off(checkpoint);
+        self.stack.push((kind, children));
+    }
+
+    fn finish_node(&mut self) {
+        let (kind, children) = self.stack.pop().unwrap();
+        self.stack.last_mut().unwrap().1.push(SyntaxElement::Node(SyntaxNode { kind, children }));
+    }
+
+    fn eat_trivia(&mut self) {
+        while self.pos < self.tokens.len() && is_trivia(self.tokens[self.pos].get_type()) {
+            self.push_token();
+        }
+    }
+
+    fn push_token(&mut self) {
+        let token = self.tokens[self.pos].clone();
+        self.pos += 1;
+        self.stack.last_mut().unwrap().1.push(SyntaxElement::Token(token));
+    }
+
+    fn current(&self) -> TokenType {
+        self.tokens[self.pos..].iter()
+            .map(Token::get_type)
+            .find(|&t| !is_trivia(t))
+            .unwrap_or(EOF)
+    }
+
+    fn at(&self, t: TokenType) -> bool {
+        self.current() == t
+    }
+
+    fn at_any(&self, types: &[TokenType]) -> bool {
+        types.contains(&self.current())
+    }
+
+    fn bump(&mut self) {
+        self.eat_trivia();
+        if self.pos < self.tokens.len() {
+            self.push_token();
+        }
+    }
+
+    fn expect(&mut self, t: TokenType, message: &str) {
+        if self.at(t) {
+            self.bump();
+        } else {
+            self.error(message);
+        }
+    }
+
+    fn error(&mut self, message: &str) {
+        self.eat_trivia();
+        let line = self.tokens.get(self.pos).map(Token::get_line).unwrap_or(0);
+        self.errors.push(format!("{} at line {}", message, line));
+    }
+
+    // Reports an error and wraps the offending token, unless it is one that an enclosing
+    // rule can still use to recover.
+    fn error_and_bump(&mut self, message: &str) {
+        self.error(message);
+        if !self.at_any(&[EOF, SEMICOLON, RIGHT_BRACE]) {
+            self.start_node(SyntaxKind::Error);
+            self.bump();
+            self.finish_node();
+        }
+    }
+
+    fn source_file(&mut self) {
+        self.stack.push((SyntaxKind::SourceFile, Vec::new()));
+        while !self.at(EOF) {
+            let pos = self.pos;
+            self.declaration();
+            if self.pos == pos {
+                self.start_node(SyntaxKind::Error);
+                self.bump();
+                self.finish_node();
+            }
+        }
+        // Trailing trivia and the EOF token itself.
+        while self.pos < self.tokens.len() {
+            self.push_token();
+        }
+    }
+
+    fn declaration(&mut self) {
+        if self.at(VAR) {
+            self.var_declaration();
+        } else if self.at(FUN) {
+            self.function_declaration();
+        } else {
+            self.statement();
+        }
+    }
+
+    fn var_declaration(&mut self) {
+        self.start_node(SyntaxKind::VarDecl);
+        self.bump();
+        self.expect(IDENTIFIER, "Expect variable name.");
+        if self.at(EQUAL) {
+            self.bump();
+            self.expression();
+        }
+        self.expect(SEMICOLON, "Expect ';' after value.");
+        self.finish_node();
+    }
+
+    fn function_declaration(&mut self) {
+        self.start_node(SyntaxKind::FunDecl);
+        self.bump();
+        self.expect(IDENTIFIER, "Expect function name.");
+        self.expect(LEFT_PAREN, "Expect '(' after function name.");
+        if !self.at(RIGHT_PAREN) {
+            self.expect(IDENTIFIER, "Expect parameter name.");
+            while self.at(COMMA) {
+                self.bump();
+                self.expect(IDENTIFIER, "Expect parameter name.");
+            }
+        }
+        self.expect(RIGHT_PAREN, "Expect ')' after parameters.");
+        if self.at(LEFT_BRACE) {
+            self.function_depth += 1;
+            self.block();
+            self.function_depth -= 1;
+        } else {
+            self.error("Expect '{' before function body.");
+        }
+        self.finish_node();
+    }
+
+    fn statement(&mut self) {
+        match self.current() {
+            PRINT => {
+                self.start_node(SyntaxKind::PrintStmt);
+                self.bump();
+                self.expression();
+                self.expect(SEMICOLON, "Expect ';' after value.");
+                self.finish_node();
+            },
+            LEFT_BRACE => self.block(),
+            IF => {
+                self.start_node(SyntaxKind::IfStmt);
+                self.bump();
+                self.expect(LEFT_PAREN, "Expected '(' after 'if'");
+                self.expression();
+                self.expect(RIGHT_PAREN, "Expected ')' after 'if' condition");
+                self.statement();
+                if self.at(ELSE) {
+                    self.bump();
+                    self.statement();
+                }
+                self.finish_node();
+            },
+            WHILE => {
+                self.start_node(SyntaxKind::WhileStmt);
+                self.bump();
+                self.expect(LEFT_PAREN, "Expected '(' after 'while'");
+                self.expression();
+                self.expect(RIGHT_PAREN, "Expected ')' after 'while' condition");
+                self.statement();
+                self.finish_node();
+            },
+            FOR => self.for_statement(),
+            RETURN => {
+                self.start_node(SyntaxKind::ReturnStmt);
+                if self.function_depth == 0 {
+                    self.error("Can't return from top-level code");
+                }
+                self.bump();
+                if !self.at(SEMICOLON) {
+                    self.expression();
+                }
+                self.expect(SEMICOLON, "Expect ';' after return value.");
+                self.finish_node();
+            },
+            _ => self.expression_statement(),
+        }
+    }
+
+    fn block(&mut self) {
+        self.start_node(SyntaxKind::Block);
+        self.bump();
+        while !self.at(RIGHT_BRACE) && !self.at(EOF) {
+            let pos = self.pos;
+            self.declaration();
+            if self.pos == pos {
+                self.error_and_bump("Unexpected token in block.");
+                if self.pos == pos {
+                    break;
+                }
+            }
+        }
+        self.expect(RIGHT_BRACE, "Expected '}' after block.");
+        self.finish_node();
+    }
+
+    fn for_statement(&mut self) {
+        self.start_node(SyntaxKind::ForStmt);
+        self.bump();
+        self.expect(LEFT_PAREN, "Expected '(' after 'for'");
+        if self.at(VAR) {
+            self.var_declaration();
+        } else if self.at(SEMICOLON) {
+            self.bump();
+        } else {
+            self.expression_statement();
+        }
+        if !self.at(SEMICOLON) {
+            self.expression();
+        }
+        self.expect(SEMICOLON, "Expect ';' after condition of for loop.");
+        if !self.at(RIGHT_PAREN) {
+            self.expression();
+        }
+        self.expect(RIGHT_PAREN, "Expect ')' after for loop.");
+        self.statement();
+        self.finish_node();
+    }
+
+    fn expression_statement(&mut self) {
+        self.start_node(SyntaxKind::ExprStmt);
+        self.expression();
+        self.expect(SEMICOLON, "Expect ';' after expression.");
+        self.finish_node();
+    }
+
+    fn expression(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.binary(0);
+        if self.at_any(ASSIGNMENT_OPERATORS) {
+            self.start_node_at(checkpoint, SyntaxKind::AssignExpr);
+            self.bump();
+            self.expression();
+            self.finish_node();
+        }
+    }
+
+    fn binary(&mut self, level: usize) {
+        let Some(operators) = BINARY_LEVELS.get(level) else {
+            return self.unary();
+        };
+        let checkpoint = self.checkpoint();
+        self.binary(level + 1);
+        while self.at_any(operators) {
+            self.start_node_at(checkpoint, SyntaxKind::BinaryExpr);
+            self.bump();
+            self.binary(level + 1);
+            self.finish_node();
+        }
+    }
+
+    fn unary(&mut self) {
+        if !self.at_any(&[BANG, MINUS, TILDE]) {
+            return self.power();
+        }
+        self.start_node(SyntaxKind::UnaryExpr);
+        self.bump();
+        self.unary();
+        self.finish_node();
+    }
+
+    fn power(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.increment();
+        if self.at(STAR_STAR) {
+            self.start_node_at(checkpoint, SyntaxKind::BinaryExpr);
+            self.bump();
+            self.unary();
+            self.finish_node();
+        }
+    }
+
+    fn increment(&mut self) {
+        if !self.at_any(&[PLUS_PLUS, MINUS_MINUS]) {
+            return self.postfix();
+        }
+        self.start_node(SyntaxKind::IncrementExpr);
+        self.bump();
+        self.call();
+        self.finish_node();
+    }
+
+    fn postfix(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.call();
+        if self.at_any(&[PLUS_PLUS, MINUS_MINUS]) {
+            self.start_node_at(checkpoint, SyntaxKind::IncrementExpr);
+            self.bump();
+            self.finish_node();
+        }
+    }
+
+    fn call(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.primary();
+        while self.at_any(&[LEFT_PAREN, LEFT_BRACKET]) {
+            if self.at(LEFT_BRACKET) {
+                self.start_node_at(checkpoint, SyntaxKind::IndexExpr);
+                self.bump();
+                self.expression();
+                self.expect(RIGHT_BRACKET, "Expect ']' after index.");
+                self.finish_node();
+                continue;
+            }
+            self.start_node_at(checkpoint, SyntaxKind::CallExpr);
+            self.bump();
+            if !self.at(RIGHT_PAREN) {
+                self.expression();
+                while self.at(COMMA) {
+                    self.bump();
+                    self.expression();
+                }
+            }
+            self.expect(RIGHT_PAREN, "Expect ')' after arguments.");
+            self.finish_node();
+        }
+    }
+
+    fn primary(&mut self) {
+        match self.current() {
+            NUMBER | STRING | TRUE | FALSE | NIL => {
+                self.start_node(SyntaxKind::LiteralExpr);
+                self.bump();
+                self.finish_node();
+            },
+            IDENTIFIER => {
+                self.start_node(SyntaxKind::VariableExpr);
+                self.bump();
+                self.finish_node();
+            },
+            LEFT_PAREN => {
+                self.start_node(SyntaxKind::GroupingExpr);
+                self.bump();
+                self.expression();
+                self.expect(RIGHT_PAREN, "Expect ')' after expression.");
+                self.finish_node();
+            },
+            INTERPOLATION => {
+                self.start_node(SyntaxKind::InterpolationExpr);
+                while self.at(INTERPOLATION) {
+                    self.bump();
+                    self.expression();
+                }
+                self.expect(STRING, "Expect end of string after interpolated expression.");
+                self.finish_node();
+            },
+            _ => self.error_and_bump("Expected expression."),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::expression::Expr;
+use crate::interpreter::Interpreter;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::statement::Stmt;
+use crate::syntax;
+use crate::token::{Token, Value};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a test script expects, read from its comments in the Crafting Interpreters test suite
+/// format:
+///
+/// - `// expect: <line>` for each line of stdout, in order;
+/// - `// expect runtime error: <message>` for a runtime error;
+/// - `// Error ...` or `// [line N] Error ...` for a scan or parse error on that line.
+///
+/// `// [java line N]` and `// [c line N]` annotations belong to the reference implementations
+/// and are ignored, and files containing `// nontest` are skipped. This interpreter words its
+/// syntax errors differently from the reference ones, so those are matched by line only.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expectations {
+    pub output: Vec<String>,
+    pub runtime_error: Option<String>,
+    pub error_lines: Vec<usize>,
+    pub skip: bool,
+}
+
+pub fn parse_expectations(src: &str) -> Expectations {
+    let mut expectations = Expectations::default();
+    for (i, line) in src.lines().enumerate() {
+        let Some((_, comment)) = line.split_once("//") else {
+            continue;
+        };
+        let comment = comment.trim_start();
+        if comment.starts_with("nontest") {
+            expectations.skip = true;
+        } else if let Some(output) = comment.strip_prefix("expect: ") {
+            expectations.output.push(output.to_string());
+        } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+            expectations.runtime_error = Some(message.to_string());
+        } else if comment.starts_with("Error") {
+            expectations.error_lines.push(i + 1);
+        } else if let Some(rest) = comment.strip_prefix("[line ")
+            && let Some((number, annotation)) = rest.split_once(']')
+            && annotation.trim_start().starts_with("Error")
+            && let Ok(number) = number.parse() {
+            expectations.error_lines.push(number);
+        }
+    }
+    expectations
+}
+
+/// Checks a script's stdout, stderr and exit code against its expectations and returns what
+/// went wrong. The exit code is `None` if the script was killed by a signal.
+pub fn check(expectations: &Expectations, stdout: &str, stderr: &str, exit_code: Option<i32>) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    let mut error_lines = Vec::new();
+    let mut runtime_errors = Vec::new();
+    for line in stderr.lines() {
+        if let Some(message) = line.strip_prefix("Runtime error: ") {
+            runtime_errors.push(message);
+        } else if let Some(number) = error_line(line) {
+            error_lines.push(number);
+        } else {
+            failures.push(format!("Unexpected output on stderr: {}", line));
+        }
+    }
+
+    // One mistake often produces several errors on the same line, so only the lines are compared.
+    let mut expected_lines = expectations.error_lines.clone();
+    expected_lines.sort_unstable();
+    expected_lines.dedup();
+    error_lines.sort_unstable();
+    error_lines.dedup();
+    if error_lines != expected_lines {
+        failures.push(format!("Expected syntax errors on lines {:?}, got {:?}.", expected_lines, error_lines));
+    }
+
+    match (&expectations.runtime_error, runtime_errors.as_slice()) {
+        (None, []) => {},
+        (None, errors) => failures.extend(errors.iter().map(|error| format!("Unexpected runtime error: {}", error))),
+        (Some(expected), errors) if errors.iter().any(|error| error.contains(expected.as_str())) => {},
+        (Some(expected), []) => failures.push(format!("Expected runtime error '{}' but got none.", expected)),
+        (Some(expected), errors) => {
+            failures.push(format!("Expected runtime error '{}' but got '{}'.", expected, errors.join("', '")))
+        },
+    }
+
+    let expected_code = if !expectations.error_lines.is_empty() {
+        65
+    } else if expectations.runtime_error.is_some() {
+        70
+    } else {
+        0
+    };
+    match exit_code {
+        Some(code) if code == expected_code => {},
+        Some(code) => failures.push(format!("Expected exit code {} but got {}.", expected_code, code)),
+        None => failures.push(format!("Expected exit code {} but the script was killed by a signal.", expected_code)),
+    }
+
+    let actual: Vec<&str> = stdout.lines().collect();
+    if actual != expectations.output {
+        failures.push("Output differs (- expected, + actual):".to_string());
+        for i in 0..actual.len().max(expectations.output.len()) {
+            match (expectations.output.get(i), actual.get(i)) {
+                (Some(expected), Some(actual)) if expected == actual => failures.push(format!("   {}", actual)),
+                (expected, actual) => {
+                    failures.extend(expected.map(|line| format!(" - {}", line)));
+                    failures.extend(actual.map(|line| format!(" + {}", line)));
+                },
+            }
+        }
+    }
+    failures
+}
+
+// The line a scan error (`[line 3:5] Error: ...`) or parse error (`Parsing error: ... at line 3`)
+// was reported on.
+fn error_line(line: &str) -> Option<usize> {
+    if let Some(rest) = line.strip_prefix("[line ") {
+        return rest.split([':', ']']).next()?.parse().ok();
+    }
+    let message = line.strip_prefix("Parsing error: ")?;
+    message.rsplit_once(" at line ")?.1.trim_end_matches('.').parse().ok()
+}
+
+/// Runs every `.lox` file under `dir` with `interpreter` and prints a report. Returns whether
+/// all of them passed.
+pub fn run_tests(dir: &Path, interpreter: &Path) -> bool {
+    let mut paths = Vec::new();
+    collect_scripts(dir, &mut paths);
+    paths.sort();
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for path in &paths {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                println!("FAIL {}\n  {}", path.display(), err);
+                failed += 1;
+                continue;
+            },
+        };
+        let expectations = parse_expectations(&src);
+        if expectations.skip {
+            skipped += 1;
+            continue;
+        }
+        let failures = match run_script(interpreter, path) {
+            Ok((stdout, stderr, exit_code)) => check(&expectations, &stdout, &stderr, exit_code),
+            Err(err) => vec![err],
+        };
+        if failures.is_empty() {
+            passed += 1;
+        } else {
+            failed += 1;
+            println!("FAIL {}", path.display());
+            for failure in failures {
+                println!("  {}", failure);
+            }
+        }
+    }
+    println!("{} passed, {} failed, {} skipped.", passed, failed, skipped);
+    failed == 0
+}
+
+/// Runs the unit tests in every `.lox` file under `dir`: each top-level function whose name
+/// starts with `test_`. Every test gets a fresh interpreter that runs the whole file and then
+/// calls the test, which fails if it raises a runtime error, such as a failed `assert`.
+/// Returns whether all of them passed.
+pub fn run_unit_tests(dir: &Path) -> bool {
+    let mut paths = Vec::new();
+    collect_scripts(dir, &mut paths);
+    paths.sort();
+
+    let (mut passed, mut failed) = (0, 0);
+    for path in &paths {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                println!("FAIL {}\n  {}", path.display(), err);
+                failed += 1;
+                continue;
+            },
+        };
+        let errors = syntax::parse(src.clone()).errors;
+        if !errors.is_empty() {
+            println!("FAIL {}", path.display());
+            for error in errors {
+                println!("  {}", error);
+            }
+            failed += 1;
+            continue;
+        }
+        let statements = Parser::new(Scanner::new(src)).parse();
+        for name in test_names(&statements) {
+            let start = Instant::now();
+            let result = run_unit_test(&statements, name);
+            let elapsed = start.elapsed();
+            match result {
+                Ok(()) => {
+                    println!("PASS {}::{} ({:.2?})", path.display(), name.get_lexeme(), elapsed);
+                    passed += 1;
+                },
+                Err(error) => {
+                    println!("FAIL {}::{} ({:.2?})\n  {}", path.display(), name.get_lexeme(), elapsed, error);
+                    failed += 1;
+                },
+            }
+        }
+    }
+    println!("{} passed, {} failed.", passed, failed);
+    failed == 0
+}
+
+// The names of a file's top-level `test_` functions, in the order they are declared.
+fn test_names(statements: &[Stmt]) -> Vec<&Token> {
+    statements.iter().filter_map(|stmt| match stmt {
+        Stmt::Function(declaration) if declaration.name.get_lexeme().starts_with("test_") => Some(&declaration.name),
+        _ => None,
+    }).collect()
+}
+
+fn run_unit_test(statements: &[Stmt], name: &Token) -> Result<(), String> {
+    let mut interpreter = Interpreter::new();
+    interpreter.define_global("args", Value::List(Rc::new(Vec::new())));
+    interpreter.run(statements)?;
+    let call = Expr::Call { callee: Box::new(Expr::Variable(name.clone())), paren: name.clone(), arguments: Vec::new() };
+    interpreter.evaluate(&call).map(|_| ())
+}
+
+fn collect_scripts(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        if dir.extension().is_some_and(|extension| extension == "lox") {
+            paths.push(dir.to_path_buf());
+        }
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_scripts(&path, paths);
+        } else if path.extension().is_some_and(|extension| extension == "lox") {
+            paths.push(path);
+        }
+    }
+}
+
+// Runs one script in a child process, killing it if it doesn't finish within `TIMEOUT`.
+fn run_script(interpreter: &Path, path: &Path) -> Result<(String, String, Option<i32>), String> {
+    let mut child = Command::new(interpreter)
+        .arg(path)
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Could not start {}: {}", interpreter.display(), err))?;
+
+    // Drain both pipes on their own threads so a chatty script can't fill one and block.
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let stdout = thread::spawn(move || {
+        let mut text = String::new();
+        stdout.read_to_string(&mut text).map(|_| text)
+    });
+    let stderr = thread::spawn(move || {
+        let mut text = String::new();
+        stderr.read_to_string(&mut text).map(|_| text)
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() > TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Timed out after {:?}.", TIMEOUT));
+            },
+            Ok(None) => thread::sleep(Duration::from_millis(5)),
+            Err(err) => return Err(err.to_string()),
+        }
+    };
+    let stdout = stdout.join().unwrap().map_err(|err| err.to_string())?;
+    let stderr = stderr.join().unwrap().map_err(|err| err.to_string())?;
+    Ok((stdout, stderr, status.code()))
+}
@@ -1,55 +1,183 @@
-#[allow(non_camel_case_types)]
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use crate::bigint::BigInt;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::statement::FunctionDecl;
+
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     // Single-character tokens.
-    LEFT_PAREN, RIGHT_PAREN, LEFT_BRACE, RIGHT_BRACE,
-    COMMA, DOT, MINUS, PLUS, SEMICOLON, SLASH, STAR,
+    LEFT_PAREN, RIGHT_PAREN, LEFT_BRACE, RIGHT_BRACE, LEFT_BRACKET, RIGHT_BRACKET,
+    COMMA, DOT, MINUS, PLUS, SEMICOLON, SLASH, STAR, PERCENT,
+    AMPERSAND, PIPE, CARET, TILDE,
 
     // One or two character tokens.
     BANG, BANG_EQUAL, EQUAL, EQUAL_EQUAL,
     GREATER, GREATER_EQUAL, LESS, LESS_EQUAL,
+    PLUS_EQUAL, MINUS_EQUAL, STAR_EQUAL, SLASH_EQUAL, PERCENT_EQUAL,
+    PLUS_PLUS, MINUS_MINUS, STAR_STAR, TILDE_SLASH,
+    LESS_LESS, GREATER_GREATER,
 
     // Literals.
     IDENTIFIER, STRING, NUMBER,
+    // A string segment that ends in `${`, followed by the tokens of the embedded expression.
+    INTERPOLATION,
 
     // Keywords.
     AND, CLASS, ELSE, FALSE, FUN, FOR, IF, NIL, OR,
     PRINT, RETURN, SUPER, THIS, TRUE, VAR, WHILE,
 
+    // Trivia, only produced by a scanner in trivia mode.
+    WHITESPACE, COMMENT, ERROR,
+
     EOF
 }
 
+impl TokenType {
+    const ALL: &'static [TokenType] = &[
+        TokenType::LEFT_PAREN, TokenType::RIGHT_PAREN, TokenType::LEFT_BRACE, TokenType::RIGHT_BRACE,
+        TokenType::LEFT_BRACKET, TokenType::RIGHT_BRACKET,
+        TokenType::COMMA, TokenType::DOT, TokenType::MINUS, TokenType::PLUS, TokenType::SEMICOLON,
+        TokenType::SLASH, TokenType::STAR, TokenType::PERCENT,
+        TokenType::AMPERSAND, TokenType::PIPE, TokenType::CARET, TokenType::TILDE,
+        TokenType::BANG, TokenType::BANG_EQUAL, TokenType::EQUAL, TokenType::EQUAL_EQUAL,
+        TokenType::GREATER, TokenType::GREATER_EQUAL, TokenType::LESS, TokenType::LESS_EQUAL,
+        TokenType::PLUS_EQUAL, TokenType::MINUS_EQUAL, TokenType::STAR_EQUAL, TokenType::SLASH_EQUAL,
+        TokenType::PERCENT_EQUAL, TokenType::PLUS_PLUS, TokenType::MINUS_MINUS, TokenType::STAR_STAR,
+        TokenType::TILDE_SLASH, TokenType::LESS_LESS, TokenType::GREATER_GREATER,
+        TokenType::IDENTIFIER, TokenType::STRING, TokenType::NUMBER, TokenType::INTERPOLATION,
+        TokenType::AND, TokenType::CLASS, TokenType::ELSE, TokenType::FALSE, TokenType::FUN, TokenType::FOR,
+        TokenType::IF, TokenType::NIL, TokenType::OR, TokenType::PRINT, TokenType::RETURN, TokenType::SUPER,
+        TokenType::THIS, TokenType::TRUE, TokenType::VAR, TokenType::WHILE,
+        TokenType::WHITESPACE, TokenType::COMMENT, TokenType::ERROR,
+        TokenType::EOF,
+    ];
+
+    /// Looks a token type up by its variant name, e.g. `"PLUS_EQUAL"`.
+    pub fn from_name(name: &str) -> Option<TokenType> {
+        Self::ALL.iter().copied().find(|t| format!("{:?}", t) == name)
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value {
+    Integer(i64),
     Number(f64),
+    BigInt(BigInt),
     String(String),
     Boolean(bool),
+    NativeFunction(NativeFunction),
+    Function(Rc<Function>),
+    // Lists can't be written in source yet; `args` is the only one.
+    List(Rc<Vec<Value>>),
     Nil
 }
 
+/// A builtin implemented in Rust and bound in the global environment.
+#[derive(Debug, Clone)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    /// The fewest and most arguments it accepts.
+    pub arity: RangeInclusive<usize>,
+    /// Called with the arguments and the line of the call.
+    pub function: fn(&mut Interpreter, &[Value], usize) -> Result<Value, String>,
+}
+
+/// A function declared in Lox, together with the scope it was declared in.
+pub struct Function {
+    pub declaration: Rc<FunctionDecl>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+// The closure usually contains the function itself, so it is left out.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.get_lexeme())
+    }
+}
+
+impl Value {
+    /// The name of the value's type, as shown by the REPL's `:type` command.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "integer",
+            Value::Number(_) => "number",
+            Value::BigInt(_) => "bigint",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::NativeFunction(_) => "native function",
+            Value::Function(_) => "function",
+            Value::List(_) => "list",
+            Value::Nil => "nil",
+        }
+    }
+
+    /// Widens either numeric variant to a float, for arithmetic that mixes the two.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            // An integer equals a float only when the float holds exactly that integer.
+            (Value::Integer(i), Value::Number(n)) | (Value::Number(n), Value::Integer(i)) => {
+                n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 && *n as i64 == *i
+            },
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Value::BigInt(a), Value::Integer(i)) | (Value::Integer(i), Value::BigInt(a)) => *a == BigInt::from(*i),
+            (Value::BigInt(a), Value::Number(n)) | (Value::Number(n), Value::BigInt(a)) => {
+                BigInt::from_f64(*n).is_some_and(|n| *a == n)
+            },
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => a.name == b.name,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     t: TokenType,
     lexeme: String,
     pub(crate) literal: Option<Value>,
     line: usize,
+    column: usize,
 }
 #[allow(dead_code)]
 impl Token {
-    pub fn new(t: TokenType, lexeme: String, literal: Option<Value>, line: usize) -> Token {
-        Token { t, lexeme, literal, line }
+    pub fn new(t: TokenType, lexeme: String, literal: Option<Value>, line: usize, column: usize) -> Token {
+        Token { t, lexeme, literal, line, column }
     }
     pub fn get_type(&self) -> TokenType {
-        self.t.clone()
+        self.t
     }
     pub fn get_lexeme(&self) -> &str {
         &self.lexeme
     }
-    pub fn get_literal(self) -> Option<Value> {
-        self.literal
+    pub fn get_literal(&self) -> Option<&Value> {
+        self.literal.as_ref()
     }
     pub fn get_line(&self) -> usize {
         self.line
     }
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
 }
@@ -0,0 +1,27 @@
+use interpreter::ast_printer::print_stmt;
+use interpreter::parser::Parser;
+use interpreter::scanner::Scanner;
+
+fn print(src: &str) -> String {
+    let statements: Vec<String> = Parser::new(Scanner::new(src.to_string())).map(|stmt| print_stmt(&stmt)).collect();
+    statements.join("\n")
+}
+
+#[test]
+fn prints_expressions_as_s_expressions() {
+    assert_eq!(print("print 1 + 2 * -x;"), "(print (+ 1 (* 2 (- x))))");
+    assert_eq!(print("x += f(2n, \"a\\n\", nil)(1.0);"), "(expr (+= x (call (call f 2n \"a\\n\" nil) 1.0)))");
+    assert_eq!(print("print \"a${x++}b\";"), "(print (interpolate \"a\" (post++ x) \"b\"))");
+    assert_eq!(print("-++x ** 2;"), "(expr (- (** (pre++ x) 2)))");
+    assert_eq!(print("a[i][0] *= --a[1];"), "(expr (*= (index (index a i) 0) (pre-- (index a 1))))");
+}
+
+#[test]
+fn indents_nested_statements() {
+    assert_eq!(print("if (a) { var b = 1; } else while (c) --c;"), "\
+(if a
+  (block
+    (var b 1))
+  (while c
+    (expr (pre-- c))))");
+}
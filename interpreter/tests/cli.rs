@@ -0,0 +1,110 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn lox(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(args)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn evaluates_inline_code_with_arguments() {
+    let output = lox(&["-e", "print 1 + 2; print args; print len(args); print args[1];", "a", "b c"], "");
+    assert_eq!(stdout(&output), "3\n[\"a\", \"b c\"]\n2\nb c\n");
+    assert_eq!(stdout(&lox(&["--eval", "print args;"], "")), "[]\n");
+}
+
+#[test]
+fn reads_the_script_from_stdin() {
+    let output = lox(&["-", "x"], "print \"from stdin\"; print args[0];");
+    assert_eq!(stdout(&output), "from stdin\nx\n");
+}
+
+#[test]
+fn skips_a_shebang_line() {
+    let path = std::env::temp_dir().join(format!("lox-shebang-{}.lox", std::process::id()));
+    fs::write(&path, "#!/usr/bin/env lox\nprint args[0];\nprint nope;\n").unwrap();
+    let output = lox(&[path.to_str().unwrap(), "first"], "");
+    fs::remove_file(&path).unwrap();
+    assert_eq!(stdout(&output), "first\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Variable nope not defined"));
+}
+
+#[test]
+fn reports_bad_indexes() {
+    let output = lox(&["-e", "print args[2]; print args[\"x\"]; print 1[0];", "a"], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Index 2 out of range for length 1 at line 1."), "{}", stderr);
+    assert!(stderr.contains("Index must be an integer at line 1."), "{}", stderr);
+    assert!(stderr.contains("Can only index lists and strings at line 1."), "{}", stderr);
+}
+
+#[test]
+fn assigns_to_list_elements() {
+    let src = "var calls = 0; fun at(i) { calls++; return i; }\n\
+        var copy = args;\n\
+        args[0] = number(args[0]); args[1] = number(args[1]);\n\
+        print args[at(0)] += 5; print calls;\n\
+        print args[at(1)]++; print calls;\n\
+        print ++args[1]; print args[1]--; print --args[1];\n\
+        args[2] -= 1;\n";
+    let output = lox(&["-e", &format!("{}print args; print copy;", src), "1", "2", "c"], "");
+    assert_eq!(stdout(&output), "6\n1\n2\n2\n4\n4\n2\n[6, 2, \"c\"]\n[\"1\", \"2\", \"c\"]\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Runtime error: Operands of '-=' must be numbers at line 7."), "{}", stderr);
+
+    let output = lox(&["-e", "args[1] += \"!\";"], "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Index 1 out of range for length 0"));
+    let output = lox(&["-e", "args[0][0] = \"x\"; print args;", "ab"], "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Can only assign to list elements"));
+    let output = lox(&["-e", "len(args)[0] += 1;"], "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid assignment target"));
+}
+
+#[test]
+fn exits_with_the_kind_of_error() {
+    assert_eq!(lox(&["-e", "print 1;"], "").status.code(), Some(0));
+    let output = lox(&["-e", "print missing; print 2;"], "");
+    assert_eq!((output.status.code(), stdout(&output)), (Some(70), "2\n".to_string()));
+    // A syntax error anywhere keeps the whole script from running.
+    let output = lox(&["-", "x"], "print 1;\nvar = 2;\nprint missing;");
+    assert_eq!((output.status.code(), stdout(&output)), (Some(65), String::new()));
+    let output = lox(&["-e", "print 1; print \"side effect\"; var x = ;"], "");
+    assert_eq!((output.status.code(), stdout(&output)), (Some(65), String::new()));
+    assert_eq!(lox(&["-e", "print \"unterminated;"], "").status.code(), Some(65));
+}
+
+#[test]
+fn reports_a_malformed_token_once() {
+    for src in ["print \"\\q\";", "var x = 1__0;", "print \"\\u{D800}\";", "print 0x;", "print 1;\n\"\\q\";", "print \"never closed;"] {
+        let stderr = String::from_utf8(lox(&["-e", src], "").stderr).unwrap();
+        assert_eq!(stderr.lines().count(), 1, "{}: {}", src, stderr);
+        assert!(stderr.contains("] Error: "), "{}: {}", src, stderr);
+    }
+    // A syntax error elsewhere is still reported.
+    let stderr = String::from_utf8(lox(&["-e", "print \"\\q\";\nvar = 1;"], "").stderr).unwrap();
+    let errors: Vec<_> = stderr.lines().collect();
+    assert!(errors.len() == 2 && errors[1].starts_with("Parsing error: ") && errors[1].contains("line 2"), "{}", stderr);
+}
+
+#[test]
+fn reports_unreadable_scripts() {
+    let missing = std::env::temp_dir().join(format!("lox-missing-{}.lox", std::process::id()));
+    let missing = missing.to_str().unwrap();
+    let modes = [vec![missing], vec!["--dump-ast", missing], vec!["--dump-tokens", "--json", missing],
+        vec!["--run-json", missing], vec!["--debug", missing]];
+    for args in modes {
+        let output = lox(&args, "");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(66), "{:?}: {}", args, stderr);
+        assert!(stderr.starts_with(&format!("{}: ", missing)), "{:?}: {}", args, stderr);
+    }
+}
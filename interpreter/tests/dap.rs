@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use interpreter::dap;
+use interpreter::json::{self, Json};
+
+// Tests run in parallel, so each script gets a file of its own.
+static SCRIPTS: AtomicUsize = AtomicUsize::new(0);
+
+fn script(src: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("lox-dap-{}-{}.lox", std::process::id(), SCRIPTS.fetch_add(1, Ordering::Relaxed)));
+    fs::write(&path, src).unwrap();
+    path
+}
+
+// Frames `requests` as a client would send them, with `$PROGRAM` standing for `path`.
+fn framed(requests: &[&str], path: &Path) -> String {
+    let mut framed = String::new();
+    for (seq, request) in requests.iter().enumerate() {
+        let request = request.replace("$PROGRAM", &path.to_str().unwrap().replace('\\', "\\\\"));
+        let body = format!("{{\"seq\":{},\"type\":\"request\",{}}}", seq + 1, request);
+        framed.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    }
+    framed
+}
+
+fn messages(mut output: &str) -> Vec<Json> {
+    let mut messages = Vec::new();
+    while let Some((header, rest)) = output.split_once("\r\n\r\n") {
+        let length: usize = header.strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+        messages.push(json::parse(&rest[..length]).unwrap());
+        output = &rest[length..];
+    }
+    messages
+}
+
+// Sends `requests` to a debug adapter for `src` and returns every message it sent back.
+fn session(src: &str, requests: &[&str]) -> Vec<Json> {
+    let path = script(src);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("--dap")
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(framed(requests, &path).as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&path).unwrap();
+    messages(&String::from_utf8(output.stdout).unwrap())
+}
+
+fn response<'a>(messages: &'a [Json], command: &str) -> &'a Json {
+    messages.iter()
+        .find(|message| message.get("command").and_then(Json::as_str) == Some(command))
+        .unwrap_or_else(|| panic!("no response to {}", command))
+}
+
+fn events<'a>(messages: &'a [Json], event: &'a str) -> impl Iterator<Item = &'a Json> + 'a {
+    messages.iter()
+        .filter(move |message| message.get("event").and_then(Json::as_str) == Some(event))
+        .map(|message| message.get("body").unwrap())
+}
+
+const SCRIPT: &str = "\
+fun square(n) {
+    var result = n * n;
+    return result;
+}
+var four = square(2);
+print four;
+";
+
+const LAUNCH: &[&str] = &[
+    r#""command":"initialize","arguments":{"adapterID":"lox"}"#,
+    r#""command":"launch","arguments":{"program":"$PROGRAM"}"#,
+    r#""command":"setBreakpoints","arguments":{"source":{"path":"$PROGRAM"},"breakpoints":[{"line":2}]}"#,
+    r#""command":"configurationDone""#,
+];
+
+#[test]
+fn stops_at_breakpoints_and_inspects_scopes() {
+    let requests = [LAUNCH, &[
+        r#""command":"stackTrace","arguments":{"threadId":1}"#,
+        r#""command":"scopes","arguments":{"frameId":0}"#,
+        r#""command":"variables","arguments":{"variablesReference":1}"#,
+        r#""command":"evaluate","arguments":{"expression":"n * 10","frameId":0}"#,
+        r#""command":"pause","arguments":{"threadId":1}"#,
+        r#""command":"continue","arguments":{"threadId":1}"#,
+        r#""command":"disconnect""#,
+    ]].concat();
+    let messages = session(SCRIPT, &requests);
+
+    assert_eq!(response(&messages, "initialize").get("success"), Some(&Json::Bool(true)));
+    let stopped: Vec<_> = events(&messages, "stopped").collect();
+    assert_eq!(stopped.len(), 1);
+    assert_eq!(stopped[0].get("reason").and_then(Json::as_str), Some("breakpoint"));
+
+    let body = response(&messages, "stackTrace").get("body").unwrap();
+    let frames = body.get("stackFrames").and_then(Json::as_array).unwrap();
+    let frames: Vec<_> = frames.iter()
+        .map(|frame| (frame.get("name").and_then(Json::as_str).unwrap(), frame.get("line").and_then(Json::as_i64).unwrap()))
+        .collect();
+    assert_eq!(frames, [("square", 2), ("<script>", 5)]);
+
+    let body = response(&messages, "scopes").get("body").unwrap();
+    let scopes: Vec<_> = body.get("scopes").and_then(Json::as_array).unwrap().iter()
+        .map(|scope| scope.get("name").and_then(Json::as_str).unwrap())
+        .collect();
+    assert_eq!(scopes, ["Locals", "Globals"]);
+
+    let body = response(&messages, "variables").get("body").unwrap();
+    let variables = body.get("variables").and_then(Json::as_array).unwrap();
+    assert_eq!(variables.len(), 1);
+    assert_eq!(variables[0].get("name").and_then(Json::as_str), Some("n"));
+    assert_eq!(variables[0].get("value").and_then(Json::as_str), Some("2"));
+
+    let body = response(&messages, "evaluate").get("body").unwrap();
+    assert_eq!(body.get("result").and_then(Json::as_str), Some("20"));
+    assert_eq!(response(&messages, "pause").get("success"), Some(&Json::Bool(false)));
+
+    let output: String = events(&messages, "output").filter_map(|body| body.get("output")?.as_str()).collect();
+    assert_eq!(output, "4\n");
+    assert_eq!(events(&messages, "terminated").count(), 1);
+}
+
+#[test]
+fn steps_and_expands_the_args_list() {
+    let requests = [
+        LAUNCH[0],
+        r#""command":"launch","arguments":{"program":"$PROGRAM","stopOnEntry":true,"args":["a","b"]}"#,
+        LAUNCH[3],
+        r#""command":"next","arguments":{"threadId":1}"#,
+        r#""command":"next","arguments":{"threadId":1}"#,
+        r#""command":"scopes","arguments":{"frameId":0}"#,
+        r#""command":"variables","arguments":{"variablesReference":1}"#,
+        r#""command":"variables","arguments":{"variablesReference":2}"#,
+        r#""command":"evaluate","arguments":{"expression":"missing","frameId":0}"#,
+        r#""command":"continue","arguments":{"threadId":1}"#,
+    ];
+    let messages = session(SCRIPT, &requests);
+
+    let reasons: Vec<_> = events(&messages, "stopped").map(|body| body.get("reason").and_then(Json::as_str).unwrap()).collect();
+    assert_eq!(reasons, ["entry", "step", "step"]);
+
+    let variables: Vec<_> = messages.iter()
+        .filter(|message| message.get("command").and_then(Json::as_str) == Some("variables"))
+        .map(|message| message.get("body").unwrap().get("variables").and_then(Json::as_array).unwrap())
+        .collect();
+    let globals: Vec<_> = variables[0].iter()
+        .map(|variable| (variable.get("name").and_then(Json::as_str).unwrap(), variable.get("value").and_then(Json::as_str).unwrap()))
+        .collect();
+    assert_eq!(globals, [("args", "[\"a\", \"b\"]"), ("four", "4"), ("square", "<fn square>")]);
+    let items: Vec<_> = variables[1].iter().map(|item| item.get("value").and_then(Json::as_str).unwrap()).collect();
+    assert_eq!(items, ["\"a\"", "\"b\""]);
+
+    let evaluate = response(&messages, "evaluate");
+    assert_eq!(evaluate.get("success"), Some(&Json::Bool(false)));
+    assert!(evaluate.get("message").and_then(Json::as_str).unwrap().contains("Variable missing not defined"));
+    assert_eq!(events(&messages, "exited").count(), 1);
+}
+
+#[test]
+fn reports_errors_as_output_and_a_failing_exit_code() {
+    let requests = [LAUNCH[0], LAUNCH[1], LAUNCH[3]];
+    let messages = session("print 1;\nvar = 2;\nprint missing;\n", &requests);
+    let errors: Vec<_> = events(&messages, "output")
+        .filter(|body| body.get("category").and_then(Json::as_str) == Some("stderr"))
+        .filter_map(|body| body.get("output")?.as_str())
+        .collect();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("Parsing error: ") && errors[0].contains("line 2"), "{:?}", errors);
+    let exited: Vec<_> = events(&messages, "exited").collect();
+    assert_eq!(exited[0].get("exitCode"), Some(&Json::Integer(65)));
+    // The script didn't run, so it printed nothing.
+    assert!(events(&messages, "output").all(|body| body.get("category").and_then(Json::as_str) == Some("stderr")));
+
+    let messages = session("print 1;\nprint missing;\n", &requests);
+    let errors: Vec<_> = events(&messages, "output")
+        .filter(|body| body.get("category").and_then(Json::as_str) == Some("stderr"))
+        .filter_map(|body| body.get("output")?.as_str())
+        .collect();
+    assert!(errors.len() == 1 && errors[0].starts_with("Runtime error: ") && errors[0].contains("missing"), "{:?}", errors);
+    let exited: Vec<_> = events(&messages, "exited").collect();
+    assert_eq!(exited[0].get("exitCode"), Some(&Json::Integer(70)));
+}
+
+#[test]
+fn locates_calls_made_by_natives() {
+    let requests = [LAUNCH, &[
+        r#""command":"stackTrace","arguments":{"threadId":1}"#,
+        r#""command":"continue","arguments":{"threadId":1}"#,
+    ]].concat();
+    let messages = session("fun boom() {\n    return 1 / 0;\n}\nassertThrows(boom);\n", &requests);
+    let body = response(&messages, "stackTrace").get("body").unwrap();
+    let frames: Vec<_> = body.get("stackFrames").and_then(Json::as_array).unwrap().iter()
+        .map(|frame| (frame.get("name").and_then(Json::as_str).unwrap(), frame.get("line").and_then(Json::as_i64).unwrap()))
+        .collect();
+    assert_eq!(frames, [("boom", 2), ("<script>", 4)]);
+}
+
+#[test]
+fn moves_breakpoints_to_the_next_statement() {
+    let requests = [
+        LAUNCH[0],
+        LAUNCH[1],
+        r#""command":"setBreakpoints","arguments":{"source":{"path":"$PROGRAM"},"breakpoints":[{"line":4},{"line":1},{"line":40}]}"#,
+        LAUNCH[3],
+        r#""command":"stackTrace","arguments":{"threadId":1}"#,
+        r#""command":"evaluate","arguments":{"expression":"four +","frameId":0}"#,
+        r#""command":"setBreakpoints","arguments":{"source":{"path":"$PROGRAM"},"breakpoints":[{"line":3}]}"#,
+        r#""command":"continue","arguments":{"threadId":1}"#,
+        r#""command":"continue","arguments":{"threadId":1}"#,
+    ];
+    let messages = session(SCRIPT, &requests);
+    let placed: Vec<_> = messages.iter()
+        .filter(|message| message.get("command").and_then(Json::as_str) == Some("setBreakpoints"))
+        .map(|message| message.get("body").unwrap().get("breakpoints").and_then(Json::as_array).unwrap().iter()
+            .map(|breakpoint| (breakpoint.get("verified").and_then(Json::as_bool).unwrap(), breakpoint.get("line").and_then(Json::as_i64).unwrap()))
+            .collect::<Vec<_>>())
+        .collect();
+    // Line 4 is the function's closing brace, so its breakpoint moves to the statement on line 5.
+    assert_eq!(placed, [vec![(true, 5), (true, 1), (false, 40)], vec![(true, 3)]]);
+
+    // It stops at line 1, then at line 3 once that breakpoint is set while paused.
+    let reasons: Vec<_> = events(&messages, "stopped").map(|body| body.get("reason").and_then(Json::as_str).unwrap()).collect();
+    assert_eq!(reasons, ["breakpoint", "breakpoint"]);
+    let body = response(&messages, "stackTrace").get("body").unwrap();
+    let top = &body.get("stackFrames").and_then(Json::as_array).unwrap()[0];
+    assert_eq!(top.get("line").and_then(Json::as_i64), Some(1));
+
+    let evaluate = response(&messages, "evaluate");
+    assert_eq!(evaluate.get("success"), Some(&Json::Bool(false)));
+    assert!(evaluate.get("message").and_then(Json::as_str).unwrap().starts_with("Parsing error: "), "{:?}", evaluate);
+}
+
+// Collects what an in-process adapter sends.
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn returns_to_the_caller_when_the_client_disconnects_while_paused() {
+    let path = script(SCRIPT);
+    let requests = [LAUNCH, &[r#""command":"disconnect""#]].concat();
+    let output = Output::default();
+    dap::run_server(io::Cursor::new(framed(&requests, &path)), output.clone());
+    fs::remove_file(&path).unwrap();
+
+    let messages = messages(&String::from_utf8(output.0.borrow().clone()).unwrap());
+    assert_eq!(response(&messages, "disconnect").get("success"), Some(&Json::Bool(true)));
+    assert_eq!(events(&messages, "stopped").count(), 1);
+    // The rest of the script never ran.
+    assert_eq!(events(&messages, "output").count(), 0);
+    assert_eq!(events(&messages, "exited").count(), 0);
+}
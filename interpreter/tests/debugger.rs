@@ -0,0 +1,73 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Tests run in parallel, so each script gets a file of its own.
+static SCRIPTS: AtomicUsize = AtomicUsize::new(0);
+
+// Runs `src` under the debugger, typing `commands` at its prompt.
+fn debug(src: &str, commands: &str) -> String {
+    let path = std::env::temp_dir().join(format!("lox-debug-{}-{}.lox", std::process::id(), SCRIPTS.fetch_add(1, Ordering::Relaxed)));
+    fs::write(&path, src).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["--debug", path.to_str().unwrap()])
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(commands.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&path).unwrap();
+    String::from_utf8(output.stdout).unwrap().replace("(debug) ", "")
+}
+
+const SCRIPT: &str = "\
+fun square(n) {
+    var result = n * n;
+    return result;
+}
+for (var i = 0; i < 3; i = i + 1) {
+    print square(i);
+}
+print \"done\";
+";
+
+#[test]
+fn stops_at_breakpoints_and_inspects_the_scope() {
+    let output = debug(SCRIPT, "break 2\ncontinue\nlocals\nbacktrace\nprint n + 1\nnext\nnext\n\ndelete 2\ncontinue\n");
+    assert_eq!(output, "\
+=> 1: fun square(n) {
+Breakpoint at line 2
+=> 2: var result = n * n;
+n = 0
+#0 square at line 2
+#1 <script> at line 6
+1
+=> 3: return result;
+0
+=> 5: for (var i = 0; i < 3; i = i + 1) {
+=> 6: print square(i);
+Deleted breakpoint at line 2
+1
+4
+done
+");
+}
+
+#[test]
+fn steps_into_calls() {
+    let output = debug(SCRIPT, "b 6\nc\ns\ns\nlocals\nq\n");
+    assert_eq!(output, "\
+=> 1: fun square(n) {
+Breakpoint at line 6
+=> 6: print square(i);
+=> 2: var result = n * n;
+=> 3: return result;
+n = 0
+result = 0
+");
+}
+
+#[test]
+fn runs_to_the_end_when_input_runs_out() {
+    assert_eq!(debug(SCRIPT, ""), "=> 1: fun square(n) {\n0\n1\n4\ndone\n");
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::process::Command;
+use interpreter::format::format;
+
+#[test]
+fn formats_in_canonical_style() {
+    let src = "// loop\nfor(var i=0;i<3;i++){print i**2; /* sq */}\nif(a)print -  -a;else{}\n\n\n\nvar s=\"${ f( 1,2 ) }\";";
+    let expected = "\
+// loop
+for (var i = 0; i < 3; i++) {
+    print i ** 2; /* sq */
+}
+if (a)
+    print - -a;
+else {}
+
+var s = \"${f(1, 2)}\";
+";
+    let formatted = format(src.to_string()).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format(formatted.clone()).unwrap(), formatted);
+}
+
+#[test]
+fn formats_function_declarations() {
+    let src = "fun  add( a,b ){return a+b;}\nfun noop(){}\nfun f(){if(x)return;}";
+    let expected = "\
+fun add(a, b) {
+    return a + b;
+}
+fun noop() {}
+fun f() {
+    if (x)
+        return;
+}
+";
+    assert_eq!(format(src.to_string()).unwrap(), expected);
+    assert!(format("return 1;".to_string()).is_err());
+}
+
+#[test]
+fn keeps_comments_after_a_trailing_comment_at_the_statement_indent() {
+    let src = "print 1; // a\n// b\nprint 2; /* c */\n// d\n{\n    print 3; // e\n    // f\n}\nvar x = // g\n    1;\n";
+    assert_eq!(format(src.to_string()).unwrap(), src);
+}
+
+#[test]
+fn refuses_scripts_with_syntax_errors() {
+    assert!(format("print (1;".to_string()).is_err());
+}
+
+#[test]
+fn check_mode_reports_files_that_would_change() {
+    let path = std::env::temp_dir().join(format!("lox-fmt-{}.lox", std::process::id()));
+    let check = || Command::new(env!("CARGO_BIN_EXE_interpreter")).args(["fmt", "--check"]).arg(&path).output().unwrap();
+
+    fs::write(&path, "print 1+2;").unwrap();
+    assert!(!check().status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "print 1+2;");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_interpreter")).arg("fmt").arg(&path).status().unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "print 1 + 2;\n");
+    assert!(check().status.success());
+    fs::remove_file(&path).unwrap();
+}
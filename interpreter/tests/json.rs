@@ -0,0 +1,114 @@
+use std::fs;
+use std::process::{Command, Output};
+use interpreter::export::{export_program, export_tokens, import_program};
+use interpreter::json::{self, Json};
+use interpreter::parser::Parser;
+use interpreter::scanner::Scanner;
+
+const SCRIPTS: &[&str] = &[
+    "var x = 1 + 2 * 3; print x; print x ~/ 2; print x / 2; print -x ** 2;",
+    "var s = \"tab\\tquote\\\" ${1 + 1} é 🌍\"; print s; print r\"\\n\" + \"\"\"\n  a\n  \"\"\";",
+    "var big = 123456789012345678901234567890n; print big * big; print bigint(9223372036854775807) + 1;",
+    "print 9223372036854775807; print 0.1 + 0.2; print 1e300 * 1e300; print 1.0; print nil; print !true;",
+    "var i = 0; while (i < 3) { i++; if (i == 2) print \"two\"; else print i; }",
+    "for (var i = 0; i < 3; i += 1) { var j = i; j = j << 2; print j | 1; }",
+    "var y; print y; y = number(\"2.5\"); print y; print undefined;",
+    "var s = \"ab\"; s[0] = \"x\"; print s[len(s) - 1]++;",
+];
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_interpreter")).args(args).output().unwrap()
+}
+
+#[test]
+fn json_values_round_trip() {
+    let text = r#"{"a":[1,-2,3.5,1e-7,true,false,null],"b":"x\"\\\n\u00e9\ud83c\udf0d","c":{}}"#;
+    let parsed = json::parse(text).unwrap();
+    assert_eq!(parsed.get("b"), Some(&Json::String("x\"\\\né🌍".to_string())));
+    assert_eq!(json::parse(&parsed.to_string()).unwrap(), parsed);
+    assert!(json::parse("[1,]").is_err());
+    assert!(json::parse("{\"a\" 1}").is_err());
+}
+
+#[test]
+fn rejects_unpaired_surrogates() {
+    assert_eq!(json::parse(r#""\udbff\udfff""#).unwrap(), Json::String("\u{10FFFF}".to_string()));
+    for text in [r#""\ud83c""#, r#""\ud83cx""#, r#""\ud83c\u0041""#, r#""\ud83c\ud83c""#, r#""\udf0d""#] {
+        let error = json::parse(text).unwrap_err();
+        assert!(error.starts_with("Unpaired surrogate at offset "), "{}: {}", text, error);
+    }
+    assert!(json::parse(r#""\u+0a1""#).unwrap_err().starts_with("Invalid \\u escape"));
+}
+
+#[test]
+fn programs_survive_export_and_import() {
+    for src in SCRIPTS {
+        let statements = Parser::new(Scanner::new(src.to_string())).parse();
+        let exported = export_program(&statements);
+        let imported = import_program(&json::parse(&exported.to_string()).unwrap()).unwrap();
+        assert_eq!(export_program(&imported), exported, "{}", src);
+    }
+}
+
+#[test]
+fn tokens_export_positions_and_literals() {
+    let exported = export_tokens(Scanner::new("var x = 2n;\n  \"s\" ∑".to_string()));
+    let tokens = exported.get("tokens").and_then(Json::as_array).unwrap();
+    assert_eq!(tokens[3].to_string(), r#"{"type":"NUMBER","lexeme":"2n","literal":{"kind":"bigint","value":"2"},"line":1,"column":9}"#);
+    assert_eq!(tokens[5].get("line"), Some(&Json::Integer(2)));
+    assert_eq!(tokens[5].get("column"), Some(&Json::Integer(3)));
+    assert_eq!(exported.get("errors").and_then(Json::as_array).map(<[Json]>::len), Some(1));
+}
+
+#[test]
+fn exported_programs_run_like_their_source() {
+    for (i, src) in SCRIPTS.iter().enumerate() {
+        let dir = std::env::temp_dir();
+        let script = dir.join(format!("lox-json-{}-{}.lox", std::process::id(), i));
+        let exported = dir.join(format!("lox-json-{}-{}.json", std::process::id(), i));
+        fs::write(&script, src).unwrap();
+
+        let direct = run(&[script.to_str().unwrap()]);
+        let dump = run(&["--dump-ast", "--json", script.to_str().unwrap()]);
+        assert!(dump.status.success());
+        fs::write(&exported, &dump.stdout).unwrap();
+        let reimported = run(&["--run-json", exported.to_str().unwrap()]);
+
+        assert_eq!(String::from_utf8_lossy(&reimported.stdout), String::from_utf8_lossy(&direct.stdout), "{}", src);
+        assert_eq!(String::from_utf8_lossy(&reimported.stderr), String::from_utf8_lossy(&direct.stderr), "{}", src);
+        fs::remove_file(&script).unwrap();
+        fs::remove_file(&exported).unwrap();
+    }
+}
+
+#[test]
+fn rejects_unknown_schema_versions() {
+    let document = json::parse(r#"{"version":2,"statements":[]}"#).unwrap();
+    assert!(import_program(&document).unwrap_err().contains("version 2"));
+}
+
+// Drops the `keyword` of print, if and while nodes, as documents written before they had one.
+fn strip_keywords(json: &mut Json) {
+    match json {
+        Json::Object(fields) => {
+            let kind = fields.iter().find(|(name, _)| name == "kind").and_then(|(_, kind)| kind.as_str().map(str::to_string));
+            if matches!(kind.as_deref(), Some("Print" | "If" | "While")) {
+                fields.retain(|(name, _)| name != "keyword");
+            }
+            fields.iter_mut().for_each(|(_, value)| strip_keywords(value));
+        },
+        Json::Array(items) => items.iter_mut().for_each(strip_keywords),
+        _ => {},
+    }
+}
+
+#[test]
+fn imports_version_1_documents_without_keywords() {
+    let src = "var i = 0;\nwhile (i < 2) {\n  if (i == 1) print \"one\"; else print i;\n  i++;\n}\n";
+    let exported = export_program(&Parser::new(Scanner::new(src.to_string())).parse());
+    let mut document = exported.clone();
+    strip_keywords(&mut document);
+    assert!(!document.to_string().contains("keyword"));
+    let imported = import_program(&document).unwrap();
+    assert_eq!(export_program(&imported), exported);
+}
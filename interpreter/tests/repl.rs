@@ -0,0 +1,55 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use interpreter::repl::is_incomplete;
+
+// Feeds `input` to the REPL on stdin and returns what it printed, without the prompts.
+fn repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap().replace("... ", "").replace("> ", "")
+}
+
+#[test]
+fn detects_unfinished_statements() {
+    for src in ["for (var i = 0;", "{ print 1;", "print (1 +", "print \"abc", "print \"\"\"\n", "/* comment", "print \"${x", "var x = 1", "print 1", "print 1 +", "1 +", "f(1,", "if (x) y"] {
+        assert!(is_incomplete(src), "{:?}", src);
+    }
+}
+
+#[test]
+fn accepts_finished_statements() {
+    for src in ["", "print 1;", "{ print 1; }", "if (x) { print 1; }", "// comment", "print 1; /* c */", "print 1); ", "1 + 2", "x", "f(1)", "x++", "print 1; 2"] {
+        assert!(!is_incomplete(src), "{:?}", src);
+    }
+}
+
+#[test]
+fn echoes_expressions_and_continues_lines() {
+    assert_eq!(repl("1 + 2\nvar x = 4;\nx = 5\nx\nfor (var i = 0;\n i < 2; i++)\n print i;\nnil\n"), "3\n5\n0\n1\n");
+}
+
+#[test]
+fn runs_meta_commands() {
+    let output = repl("var s = \"a\";\n:type s\n:type 2n ** 70\n:ast -x * (1 + 2)\n:env\n:reset\n:env\n:quit\nprint 1;\n");
+    assert_eq!(output, "\
+string
+bigint
+(* (- x) (group (+ 1 2)))
+assert = <native fn assert>
+assertEqual = <native fn assertEqual>
+assertThrows = <native fn assertThrows>
+bigint = <native fn bigint>
+len = <native fn len>
+number = <native fn number>
+s = \"a\"
+assert = <native fn assert>
+assertEqual = <native fn assertEqual>
+assertThrows = <native fn assertThrows>
+bigint = <native fn bigint>
+len = <native fn len>
+number = <native fn number>
+");
+}
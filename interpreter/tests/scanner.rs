@@ -0,0 +1,104 @@
+use interpreter::bigint::BigInt;
+use interpreter::scanner::Scanner;
+use interpreter::token::Value;
+
+// The literal of the first token in `src`, or the first scan error's message and column.
+fn literal(src: &str) -> Result<Value, (String, usize)> {
+    match Scanner::new(src.to_string()).next().unwrap() {
+        Ok(token) => Ok(token.get_literal().cloned().unwrap_or(Value::Nil)),
+        Err(error) => Err((error.message, error.column)),
+    }
+}
+
+fn error(src: &str) -> String {
+    literal(src).unwrap_err().0
+}
+
+#[test]
+fn scans_number_literal_forms() {
+    assert_eq!(literal("0xFF"), Ok(Value::Integer(255)));
+    assert_eq!(literal("0b1010"), Ok(Value::Integer(10)));
+    assert_eq!(literal("0o17"), Ok(Value::Integer(15)));
+    assert_eq!(literal("1_000_000"), Ok(Value::Integer(1_000_000)));
+    assert_eq!(literal("1e9"), Ok(Value::Number(1e9)));
+    assert_eq!(literal("2.5E-3"), Ok(Value::Number(0.0025)));
+    assert_eq!(literal("1_0.5_0"), Ok(Value::Number(10.5)));
+    assert_eq!(literal("9223372036854775807"), Ok(Value::Integer(i64::MAX)));
+    assert_eq!(literal("0xFFn"), Ok(Value::BigInt(BigInt::from(255))));
+}
+
+#[test]
+fn reports_malformed_number_literals() {
+    assert_eq!(error("0x"), "Missing digits after radix prefix in number literal '0x'.");
+    assert_eq!(error("1e"), "Missing exponent digits in number literal '1e'.");
+    assert_eq!(error("1e+"), "Missing exponent digits in number literal '1e+'.");
+    assert_eq!(error("1_"), "Trailing digit separator in number literal '1_'.");
+    assert_eq!(error("1__0"), "Misplaced digit separator in number literal '1__0'.");
+    assert_eq!(error("0x_1"), "Misplaced digit separator in number literal '0x_1'.");
+    assert_eq!(error("0b102"), "Invalid digit '2' for base 2 in number literal '0b102'.");
+    assert_eq!(error("0o9"), "Invalid digit '9' for base 8 in number literal '0o9'.");
+    assert_eq!(error("1.5n"), "A bigint can't have a fraction or exponent in number literal '1.5n'.");
+    // The error points at the start of the literal.
+    assert_eq!(literal("   0x"), Err(("Missing digits after radix prefix in number literal '0x'.".to_string(), 4)));
+}
+
+#[test]
+fn rejects_integer_literals_beyond_i64() {
+    let too_large = "Integer out of range (use an 'n' suffix for a bigint)";
+    assert_eq!(error("9223372036854775808"), format!("{} in number literal '9223372036854775808'.", too_large));
+    assert_eq!(error("0x1_0000_0000_0000_0000"), format!("{} in number literal '0x1_0000_0000_0000_0000'.", too_large));
+    // A literal has no sign, so i64::MIN can't be written as `-9223372036854775808`; it is
+    // `-9223372036854775807 - 1` instead, or a bigint.
+    assert!(literal("9223372036854775808n").is_ok());
+}
+
+fn string(src: &str) -> Value {
+    literal(src).unwrap()
+}
+
+#[test]
+fn decodes_escape_sequences() {
+    assert_eq!(string(r#""a\nb\tc\rd""#), Value::String("a\nb\tc\rd".to_string()));
+    assert_eq!(string(r#""\\ \" \0 \$""#), Value::String("\\ \" \0 $".to_string()));
+    assert_eq!(string(r#""\u{41}\u{e9}\u{1F30D}""#), Value::String("Aé🌍".to_string()));
+    assert_eq!(string(r#""\u{000041}""#), Value::String("A".to_string()));
+}
+
+#[test]
+fn reports_invalid_escapes_at_their_column() {
+    assert_eq!(literal(r#""ab\q""#), Err(("Invalid escape sequence '\\q'.".to_string(), 4)));
+    assert_eq!(literal(r#""é\q""#), Err(("Invalid escape sequence '\\q'.".to_string(), 3)));
+    assert_eq!(literal(r#""\u41""#), Err(("Expected '{' after '\\u'.".to_string(), 2)));
+    assert_eq!(literal(r#""x\u{}""#), Err(("Unicode escape '\\u{}' must have 1 to 6 hex digits.".to_string(), 3)));
+    assert_eq!(literal(r#""\u{1000000}""#), Err(("Unicode escape '\\u{1000000}' must have 1 to 6 hex digits.".to_string(), 2)));
+    assert_eq!(literal(r#""\u{41""#), Err(("Expected hex digits and '}' in '\\u{...}' escape.".to_string(), 2)));
+    assert_eq!(literal(r#""\u{D800}""#), Err(("Invalid Unicode scalar value '\\u{D800}'.".to_string(), 2)));
+    assert_eq!(literal(r#""\u{110000}""#), Err(("Invalid Unicode scalar value '\\u{110000}'.".to_string(), 2)));
+
+    let error = Scanner::new("\"a\\\nb\";".to_string()).next().unwrap().unwrap_err();
+    assert_eq!((error.message.as_str(), error.line, error.column), ("Invalid escape sequence: a backslash can't end a line.", 1, 3));
+}
+
+#[test]
+fn dedents_triple_quoted_strings() {
+    assert_eq!(string("\"\"\"\n    a\n\n      b\n    \"\"\""), Value::String("a\n\n  b".to_string()));
+    assert_eq!(string("\"\"\"\r\n  a\r\n  \"\"\""), Value::String("a".to_string()));
+    assert_eq!(string("\"\"\"one line\"\"\""), Value::String("one line".to_string()));
+    assert_eq!(string("\"\"\"\n  a \\\"\"\" b\n  \"\"\""), Value::String("a \"\"\" b".to_string()));
+}
+
+#[test]
+fn keeps_backslashes_in_raw_strings() {
+    assert_eq!(string(r#"r"C:\new\u{41}""#), Value::String(r"C:\new\u{41}".to_string()));
+    assert_eq!(string("r\"\"\"\n  \\n ${x}\n  \"\"\""), Value::String("\\n ${x}".to_string()));
+}
+
+#[test]
+fn counts_lines_inside_multi_line_strings() {
+    let src = "var s = \"\"\"\n  a\n  b\n  \"\"\";\nprint s;";
+    let tokens = Scanner::new(src.to_string()).scan_tokens();
+    let print = tokens.iter().find(|token| token.get_lexeme() == "print").unwrap();
+    assert_eq!((print.get_line(), print.get_column()), (5, 1));
+    let semicolon = tokens.iter().find(|token| token.get_lexeme() == ";").unwrap();
+    assert_eq!((semicolon.get_line(), semicolon.get_column()), (4, 6));
+}
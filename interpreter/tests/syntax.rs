@@ -0,0 +1,37 @@
+use interpreter::syntax::{self, SyntaxKind};
+
+const SOURCES: &[&str] = &[
+    "",
+    "\u{FEFF}print 1;\n",
+    "// leading comment\nvar x = 1; /* trailing */\n\n  print x ;  // end",
+    "for (var i = 0; i < 3; i++) { print i ** 2; }\n",
+    "if (a) { b = -c ~/ 2; } else /* else */ print \"s ${x + \"${y}\"} t\";",
+    "print r\"\\n\" + \"\"\"\n    a\n    b\n    \"\"\";",
+    "var = ; print (1 + ; } x(1, 2 ∑ \"unterminated",
+    "/* a /* nested */ comment */ print f(1)(2);",
+];
+
+#[test]
+fn round_trips_source_text() {
+    for src in SOURCES {
+        let tree = syntax::parse(src.to_string());
+        assert_eq!(tree.root.text(), *src);
+    }
+}
+
+#[test]
+fn keeps_comments_and_for_loops() {
+    let tree = syntax::parse("// hi\nfor (;;) print 1;".to_string());
+    assert!(tree.errors.is_empty(), "{:?}", tree.errors);
+    let statements: Vec<SyntaxKind> = tree.root.child_nodes().map(|node| node.kind).collect();
+    assert_eq!(statements, [SyntaxKind::ForStmt]);
+    assert_eq!(tree.root.child_nodes().next().unwrap().text(), "for (;;) print 1;");
+    assert!(tree.root.child_tokens().any(|token| token.get_lexeme() == "// hi"));
+}
+
+#[test]
+fn reports_errors_without_losing_text() {
+    let tree = syntax::parse("print ;".to_string());
+    assert_eq!(tree.errors.len(), 1);
+    assert_eq!(tree.root.text(), "print ;");
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::process::{Command, Output};
+use interpreter::test_runner::{self, Expectations};
+
+fn lox_test(dir: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_interpreter")).args(["test", dir]).output().unwrap()
+}
+
+#[test]
+fn reads_expectation_comments() {
+    let src = "print 1; // expect: 1\n// [line 4] Error at 'x': oops\nvar a = ; // Error at ';'\n\
+        print b; // expect runtime error: Undefined variable 'b'.\n// [java line 9] Error: ignored\n";
+    assert_eq!(test_runner::parse_expectations(src), Expectations {
+        output: vec!["1".to_string()],
+        runtime_error: Some("Undefined variable 'b'.".to_string()),
+        error_lines: vec![4, 3],
+        skip: false,
+    });
+    assert!(test_runner::parse_expectations("// nontest\nprint 1;").skip);
+}
+
+#[test]
+fn checks_output_and_errors() {
+    let expectations = test_runner::parse_expectations("print 1; // expect: 1\nprint 2; // expect: 2\n");
+    assert!(test_runner::check(&expectations, "1\n2\n", "", Some(0)).is_empty());
+    assert_eq!(test_runner::check(&expectations, "1\n3\n", "", Some(0)), [
+        "Output differs (- expected, + actual):", "   1", " - 2", " + 3",
+    ]);
+    assert_eq!(test_runner::check(&expectations, "1\n2\n", "Parsing error: Expected expression. at line 2", Some(65)), [
+        "Expected syntax errors on lines [], got [2].",
+        "Expected exit code 0 but got 65.",
+    ]);
+}
+
+#[test]
+fn checks_the_exit_code() {
+    let expectations = test_runner::parse_expectations("print missing; // expect runtime error: missing
+");
+    assert!(test_runner::check(&expectations, "", "Runtime error: Variable missing not defined", Some(70)).is_empty());
+    assert_eq!(test_runner::check(&expectations, "", "Runtime error: Variable missing not defined", Some(0)), [
+        "Expected exit code 70 but got 0.",
+    ]);
+    let expectations = test_runner::parse_expectations("var = 1; // Error at '=': Expect variable name.\n");
+    assert_eq!(test_runner::check(&expectations, "", "Parsing error: Expected variable name. at line 1", None), [
+        "Expected exit code 65 but the script was killed by a signal.",
+    ]);
+}
+
+#[test]
+fn passes_the_bundled_suite() {
+    let output = lox_test(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/lox"));
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", report);
+    assert!(report.ends_with(" passed, 0 failed, 0 skipped.\n"), "{}", report);
+}
+
+#[test]
+fn reports_failures_with_a_diff() {
+    let dir = std::env::temp_dir().join(format!("lox-test-runner-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("good.lox"), "print 1 + 1; // expect: 2\n").unwrap();
+    fs::write(dir.join("bad.lox"), "print \"a\"; // expect: a\nprint \"b\"; // expect: c\n").unwrap();
+    let output = lox_test(dir.to_str().unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(report.contains("FAIL ") && report.contains("bad.lox\n"), "{}", report);
+    assert!(report.contains("   a\n   - c\n   + b\n"), "{}", report);
+    assert!(report.ends_with("1 passed, 1 failed, 0 skipped.\n"), "{}", report);
+}
+
+#[test]
+fn runs_discovered_unit_tests() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["test", "--unit", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/unit")])
+        .output().unwrap();
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", report);
+    assert!(report.contains("functions.lox::test_add ("), "{}", report);
+    assert!(report.ends_with("4 passed, 0 failed.\n"), "{}", report);
+}
+
+#[test]
+fn reports_failed_assertions_with_their_line() {
+    let path = std::env::temp_dir().join(format!("lox-unit-{}.lox", std::process::id()));
+    fs::write(&path, "var calls = 0;\nfun helper() {}\n\
+        fun test_isolated() {\n    calls += 1;\n    assertEqual(calls, 2);\n}\n\
+        fun test_assert() { assert(nil, \"nothing here\"); }\n\
+        fun test_throws() { assertThrows(helper); }\n\
+        fun test_passes() { assertEqual(assertThrows(test_assert), \"Assertion failed: nothing here at line 7.\"); }\n\
+        fun test_bare_assert() { assert(1 > 2); }\n\
+        fun test_assert_arity() { assert(); }\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["test", "--unit", path.to_str().unwrap()])
+        .output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(report.contains("::test_isolated (") && report.contains("\n  Expected 2 but got 1 at line 5.\n"), "{}", report);
+    assert!(report.contains("\n  Assertion failed: nothing here at line 7.\n"), "{}", report);
+    assert!(report.contains("\n  Expected a runtime error but the function returned nil at line 8.\n"), "{}", report);
+    assert!(report.contains("PASS ") && report.contains("::test_passes ("), "{}", report);
+    assert!(report.contains("\n  Assertion failed at line 10.\n"), "{}", report);
+    assert!(report.contains("\n  Expected 1 to 2 arguments but got 0 at line 11.\n"), "{}", report);
+    assert!(report.ends_with("1 passed, 5 failed.\n"), "{}", report);
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::process::Command;
+use interpreter::scanner::Scanner;
+use interpreter::token::TokenType;
+
+// Runs `src` as a script file through the interpreter binary and returns (stdout, stderr).
+fn run_script(name: &str, src: &[u8]) -> (String, String) {
+    let path = std::env::temp_dir().join(format!("lox-unicode-{}-{}.lox", name, std::process::id()));
+    fs::write(&path, src).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter")).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn identifiers_in_non_latin_scripts() {
+    let src = "var 名前 = 1; var привет = 2; var π = 3; var नमस्ते = 4; var _x1é = 5;";
+    let tokens = Scanner::new(src.to_string()).scan_tokens();
+    let identifiers: Vec<&str> = tokens.iter()
+        .filter(|token| token.get_type() == TokenType::IDENTIFIER)
+        .map(|token| token.get_lexeme())
+        .collect();
+    assert_eq!(identifiers, ["名前", "привет", "π", "नमस्ते", "_x1é"]);
+}
+
+#[test]
+fn prints_unicode_values_and_names() {
+    let (stdout, stderr) = run_script("print", "\
+        var 挨拶 = \"こんにちは\";\n\
+        var мир = \"мир\";\n\
+        print 挨拶 + \", \" + мир + \" 🌍\";\n\
+        var Δ = 2; Δ *= 21; print Δ;\n\
+        // комментарий /* 注释 */\n\
+        print \"${挨拶}\";\n".as_bytes());
+    assert_eq!(stderr, "");
+    assert_eq!(stdout, "こんにちは, мир 🌍\n42\nこんにちは\n");
+}
+
+#[test]
+fn skips_byte_order_mark() {
+    let (stdout, stderr) = run_script("bom", "\u{FEFF}print \"ok\";\n".as_bytes());
+    assert_eq!(stderr, "");
+    assert_eq!(stdout, "ok\n");
+}
+
+#[test]
+fn reports_symbols_that_cannot_start_identifiers() {
+    let (_, stderr) = run_script("symbol", "var ∑ = 1;\n".as_bytes());
+    assert!(stderr.contains("[line 1:5] Error: Unrecognized character '∑'."), "{}", stderr);
+}
+
+#[test]
+fn indexes_strings_by_character() {
+    let (stdout, stderr) = run_script("index", "var s = \"héllo\";\nprint s[1];\nprint len(s);\nprint s[5];\n".as_bytes());
+    assert_eq!(stdout, "é\n5\n");
+    assert!(stderr.contains("Index 5 out of range for length 5 at line 4."), "{}", stderr);
+}